@@ -0,0 +1,1074 @@
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{self, ExitCode},
+};
+
+use chrono::{Local, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use itertools::Itertools;
+
+use lazytodo::{
+    dates, editor,
+    entities::{todo, workspace},
+    formats::{
+        csv::{self, Column},
+        ics,
+        json::{self, TodoRecord},
+        markdown, org, taskwarrior, todotxt,
+    },
+    scanner, stats,
+    store::{self, Location},
+    sync, Link, Store, Todo, Workspace,
+};
+
+use crate::{config::Config, seed};
+
+const AFTER_HELP: &str = "Without a command the interactive interface is started.
+
+Exit status: 0 on success, 1 when a todo or workspace does not exist or the store \
+cannot be read or written, 2 on invalid usage.";
+
+#[derive(Parser)]
+#[command(version, about = "Todos in the terminal", after_help = AFTER_HELP)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Add a todo and print its id
+    Add {
+        text: String,
+        /// Due date: today, tomorrow, fri, +3d, +2w or YYYY-MM-DD
+        #[arg(long)]
+        due: Option<String>,
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Workspace title or id
+        #[arg(long, short)]
+        workspace: Option<String>,
+        /// File location such as src/main.rs:412, found in the text when not given
+        #[arg(long)]
+        link: Option<String>,
+    },
+    /// List todos
+    List {
+        /// e.g. "open #ops @infra due:fri deploy"
+        #[arg(long, short, default_value = "")]
+        filter: String,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Mark todos as done
+    Done {
+        #[arg(required = true)]
+        ids: Vec<usize>,
+        /// Mark them as not done instead
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Delete todos
+    Rm {
+        #[arg(required = true)]
+        ids: Vec<usize>,
+    },
+    /// Change a todo, opening its text in $EDITOR when no option is given
+    Edit(EditArgs),
+    /// Open the file linked to a todo in the editor
+    Open { id: usize },
+    /// Manage workspaces
+    #[command(subcommand)]
+    Workspace(WorkspaceCommand),
+    /// Write the store to stdout or a file, archived todos are only in the json format
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Columns of the csv format, from id, workspace, text, done, created_at,
+        /// completed_at, due, tags and priority
+        #[arg(long, value_delimiter = ',', default_values_t = Column::ALL)]
+        columns: Vec<Column>,
+    },
+    /// Add todos read from a file or stdin
+    Import {
+        #[arg(long, value_enum, default_value_t = ImportFormat::Jsonl)]
+        format: ImportFormat,
+        /// Defaults to stdin, also read for "-"
+        file: Option<PathBuf>,
+        /// Leave todos whose ics UID is already in the store unchanged instead of updating them
+        #[arg(long)]
+        skip_existing: bool,
+        /// Report what would be imported without saving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Merge the Markdown files bound to workspaces into the store and write them back
+    Sync,
+    /// Count created and completed todos, archived ones included
+    Stats {
+        #[arg(long, value_enum, default_value_t = StatsBy::Day)]
+        by: StatsBy,
+        #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+    /// TODO, FIXME and HACK comments in the source code under the current directory
+    #[command(subcommand)]
+    Code(CodeCommand),
+    /// Fill a new store with fake workspaces and todos, for demos and trying large lists
+    Seed {
+        /// Directory of the store, created when missing
+        #[arg(default_value = "lazytodo-demo")]
+        dir: PathBuf,
+        #[arg(long, default_value_t = 5)]
+        workspaces: usize,
+        #[arg(long, default_value_t = 5000)]
+        todos: usize,
+        /// The same seed and --date give the same store
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Day the dates are relative to, today when not given. Pass it to get the same store
+        /// on another day
+        #[arg(long)]
+        date: Option<String>,
+        /// Replace the todos and workspaces of an existing store
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct EditArgs {
+    id: usize,
+    #[arg(long)]
+    text: Option<String>,
+    /// Due date, or "none" to clear it
+    #[arg(long)]
+    due: Option<String>,
+    /// Tag to add
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+    /// Tag to remove
+    #[arg(long = "untag", value_name = "TAG")]
+    untags: Vec<String>,
+    /// Workspace title or id, or "none" to take the todo out of its workspace
+    #[arg(long, short)]
+    workspace: Option<String>,
+    /// File location such as src/main.rs:412, or "none" to clear it
+    #[arg(long)]
+    link: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum WorkspaceCommand {
+    /// Add a workspace and print its id
+    Add {
+        title: String,
+        /// Title or id of the parent workspace
+        #[arg(long)]
+        parent: Option<String>,
+    },
+    /// List workspaces with their open and done todo counts
+    Ls,
+    /// Delete a workspace, its todos and sub-workspaces move to its parent
+    Rm { workspace: String },
+    /// Keep the todos of a workspace in sync with a Markdown checklist such as TODO.md
+    Bind { workspace: String, file: String },
+    /// Stop syncing a workspace with its file
+    Unbind { workspace: String },
+}
+
+#[derive(Subcommand)]
+pub enum CodeCommand {
+    /// List the comments, skipping files ignored by .gitignore
+    Ls {
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Add a todo linked to a comment and print its id
+    Promote {
+        /// Location of the comment, e.g. src/main.rs:42
+        location: String,
+        /// Workspace title or id
+        #[arg(long, short)]
+        workspace: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+    Plain,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// The whole store in one object
+    Json,
+    /// One todo per line, as read by `import --format jsonl`
+    Jsonl,
+    Todotxt,
+    Markdown,
+    /// iCalendar VTODOs
+    Ics,
+    /// Emacs Org-mode headlines
+    Org,
+    /// Comma separated values, see --columns
+    Csv,
+}
+
+impl ExportFormat {
+    /// The format of a file after its extension, e.g. Markdown for `todos.md`.
+    fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "jsonl" => Some(Self::Jsonl),
+            "txt" => Some(Self::Todotxt),
+            "md" | "markdown" => Some(Self::Markdown),
+            "ics" => Some(Self::Ics),
+            "org" => Some(Self::Org),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StatsBy {
+    /// Todos created and completed on each day
+    Day,
+    /// Open and done todos of each workspace
+    Workspace,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StatsFormat {
+    Table,
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ImportFormat {
+    /// One JSON todo per line, replacing the todo with the same id
+    Jsonl,
+    Todotxt,
+    /// iCalendar VTODOs, updating the todo with the same UID
+    Ics,
+    /// The output of `task export`, projects become nested workspaces
+    Taskwarrior,
+    /// Emacs Org-mode headlines, replacing the todo with the same LAZYTODO_ID
+    Org,
+}
+
+pub fn run(command: Commands) -> ExitCode {
+    // The fake store goes in its own directory, the one here is left alone.
+    if let Commands::Seed {
+        dir,
+        workspaces,
+        todos,
+        seed,
+        date,
+        force,
+    } = command
+    {
+        return exit_code(seed_store(&dir, workspaces, todos, seed, date, force));
+    }
+
+    // Other lazytodo processes wait for the command to be done with the store.
+    let location = Location::default();
+    let _lock = match store::lock(&location) {
+        Ok(lock) => lock,
+        Err(err) => {
+            eprintln!("lazytodo: {}: {err}", location.lock().display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Edits made in the bound files are picked up before the store changes, as the files
+    // are written back after it. Commands that only read leave the files alone.
+    if command.writes() {
+        if let Err(err) = merge_files(true) {
+            eprintln!("lazytodo: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let result = match command {
+        Commands::Add {
+            text,
+            due,
+            tags,
+            workspace,
+            link,
+        } => add(text, due, tags, workspace, link),
+        Commands::List { filter, format } => list(&filter, format),
+        Commands::Done { ids, undo } => set_done(&ids, !undo),
+        Commands::Rm { ids } => remove(&ids),
+        Commands::Edit(args) => edit(args),
+        Commands::Open { id } => open(id),
+        Commands::Workspace(WorkspaceCommand::Add { title, parent }) => {
+            add_workspace(title, parent)
+        }
+        Commands::Workspace(WorkspaceCommand::Ls) => list_workspaces(),
+        Commands::Workspace(WorkspaceCommand::Rm { workspace }) => remove_workspace(&workspace),
+        Commands::Workspace(WorkspaceCommand::Bind { workspace, file }) => {
+            bind_workspace(&workspace, Some(file))
+        }
+        Commands::Workspace(WorkspaceCommand::Unbind { workspace }) => {
+            bind_workspace(&workspace, None)
+        }
+        Commands::Sync => load_todos()
+            .and_then(|todos| save_todos(&todos))
+            .map_err(Error::from),
+        Commands::Export {
+            format,
+            output,
+            columns,
+        } => export(format, output, &columns),
+        Commands::Stats { by, format } => print_stats(by, format),
+        Commands::Import {
+            format,
+            file,
+            skip_existing,
+            dry_run,
+        } => import(format, file, skip_existing, dry_run),
+        Commands::Code(CodeCommand::Ls { format }) => list_code_todos(format),
+        Commands::Code(CodeCommand::Promote {
+            location,
+            workspace,
+        }) => promote_code_todo(&location, workspace),
+        Commands::Seed { .. } => unreachable!("handled before taking the lock"),
+    };
+    exit_code(result)
+}
+
+impl Commands {
+    /// Whether the command can change the store.
+    fn writes(&self) -> bool {
+        match self {
+            Commands::List { .. }
+            | Commands::Open { .. }
+            | Commands::Workspace(WorkspaceCommand::Ls)
+            | Commands::Export { .. }
+            | Commands::Stats { .. }
+            | Commands::Code(CodeCommand::Ls { .. })
+            | Commands::Seed { .. } => false,
+            Commands::Import { dry_run, .. } => !dry_run,
+            _ => true,
+        }
+    }
+}
+
+/// Why a command failed, which decides its exit status.
+enum Error {
+    /// Invalid arguments, such as a date that cannot be read.
+    Usage(String),
+    /// A todo or workspace that does not exist, or a store that cannot be read or written.
+    Failure(String),
+}
+
+impl From<String> for Error {
+    fn from(err: String) -> Self {
+        Self::Failure(err)
+    }
+}
+
+fn exit_code(result: Result<(), Error>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Error::Usage(err)) => {
+            eprintln!("lazytodo: {err}");
+            ExitCode::from(2)
+        }
+        Err(Error::Failure(err)) => {
+            eprintln!("lazytodo: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn load_todos() -> Result<Vec<Todo>, String> {
+    let path = Location::default().todos();
+    store::load_todos(&path).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// Saves the store and writes the todos back to the files bound to workspaces.
+fn save_todos(todos: &[Todo]) -> Result<(), String> {
+    let path = Location::default().todos();
+    store::save_todos(todos, &path).map_err(|err| format!("{}: {err}", path.display()))?;
+    for err in sync::write_files(todos, &load_workspaces()?) {
+        eprintln!("lazytodo: {err}");
+    }
+    Ok(())
+}
+
+fn merge_files(remove_missing: bool) -> Result<(), String> {
+    let mut todos = load_todos()?;
+    let workspaces = load_workspaces()?;
+    let (changed, errors) = sync::merge_files(&mut todos, &workspaces, remove_missing);
+    for err in errors {
+        eprintln!("lazytodo: {err}");
+    }
+    match changed {
+        true => save_todos(&todos),
+        false => Ok(()),
+    }
+}
+
+fn load_workspaces() -> Result<Vec<Workspace>, String> {
+    let path = Location::default().workspaces();
+    store::load_workspaces(&path).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn save_workspaces(workspaces: &[Workspace]) -> Result<(), String> {
+    let path = Location::default().workspaces();
+    store::save_workspaces(workspaces, &path).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn load_archive(location: &Location) -> Result<Vec<Todo>, String> {
+    let path = location.archive();
+    store::load_todos(&path).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn find_workspace<'a>(workspaces: &'a [Workspace], name: &str) -> Result<&'a Workspace, String> {
+    workspace::find(workspaces, name).ok_or_else(|| format!("no workspace named `{name}`"))
+}
+
+fn parse_due(date: &str) -> Result<i64, Error> {
+    let today = Local::now().date_naive();
+    dates::parse_date(date, today)
+        .map(dates::to_timestamp)
+        .ok_or_else(|| Error::Usage(format!("`{date}` is not a date")))
+}
+
+fn parse_link(link: &str) -> Result<Link, Error> {
+    link.parse().map_err(Error::Usage)
+}
+
+fn find_todo(todos: &mut [Todo], id: usize) -> Result<&mut Todo, String> {
+    todos
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("no todo with id {id}"))
+}
+
+fn add(
+    text: String,
+    due: Option<String>,
+    tags: Vec<String>,
+    workspace: Option<String>,
+    link: Option<String>,
+) -> Result<(), Error> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err(Error::Usage(String::from("the todo text is empty")));
+    }
+    let mut todos = load_todos()?;
+    let workspaces = load_workspaces()?;
+
+    let mut todo = Todo::new(todo::next_id(&todos), text);
+    todo.due = due.as_deref().map(parse_due).transpose()?;
+    todo.workspace = match workspace {
+        Some(name) => Some(find_workspace(&workspaces, &name)?.id),
+        None => None,
+    };
+    for tag in tags {
+        add_tag(&mut todo, &tag);
+    }
+    todo.link = match link {
+        Some(link) => Some(parse_link(&link)?),
+        None => todo::find_link(&todo.text),
+    };
+
+    let id = todo.id;
+    todos.push(todo);
+    save_todos(&todos)?;
+    println!("{id}");
+    Ok(())
+}
+
+fn add_tag(todo: &mut Todo, tag: &str) {
+    let tag = todo::normalize_tag(tag);
+    if !tag.is_empty() && !todo.tags.contains(&tag) {
+        todo.tags.push(tag);
+    }
+}
+
+fn list(filter: &str, format: Format) -> Result<(), Error> {
+    let store = Store {
+        todos: load_todos()?,
+        workspaces: load_workspaces()?,
+    };
+    let todos = store
+        .query(filter, Local::now().date_naive())
+        .map_err(Error::Usage)?;
+    let workspaces = &store.workspaces;
+
+    let workspace_title = |todo: &Todo| {
+        todo.workspace
+            .and_then(|id| workspaces.iter().find(|w| w.id == id))
+            .map(|w| w.title.as_str())
+    };
+    let due = |todo: &Todo| {
+        todo.due
+            .and_then(dates::from_timestamp)
+            .map(|d| d.format("%Y-%m-%d").to_string())
+    };
+
+    match format {
+        Format::Json => {
+            let records: Vec<TodoRecord> = todos.iter().copied().map(TodoRecord::from).collect();
+            let json = serde_json::to_string_pretty(&records).map_err(|err| err.to_string())?;
+            println!("{json}");
+        }
+        Format::Plain => {
+            for todo in todos {
+                println!("{}\t{}", todo.id, todo.text);
+            }
+        }
+        Format::Table => {
+            let rows: Vec<[String; 5]> = todos
+                .iter()
+                .map(|todo| {
+                    let text = todo.display_text();
+                    [
+                        todo.id.to_string(),
+                        String::from(if todo.done { "[x]" } else { "[ ]" }),
+                        due(todo).unwrap_or_default(),
+                        workspace_title(todo).unwrap_or_default().to_string(),
+                        text,
+                    ]
+                })
+                .collect();
+            print_table(["ID", "", "DUE", "WORKSPACE", "TODO"], &rows);
+        }
+    }
+    Ok(())
+}
+
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(|h| h.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let print_row = |cells: Vec<&str>| {
+        let line = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .join("  ");
+        println!("{}", line.trim_end());
+    };
+    print_row(header.to_vec());
+    for row in rows {
+        print_row(row.iter().map(String::as_str).collect());
+    }
+}
+
+fn set_done(ids: &[usize], done: bool) -> Result<(), Error> {
+    let mut todos = load_todos()?;
+    for id in ids {
+        find_todo(&mut todos, *id)?.set_done(done);
+    }
+    Ok(save_todos(&todos)?)
+}
+
+fn remove(ids: &[usize]) -> Result<(), Error> {
+    let mut todos = load_todos()?;
+    if let Some(id) = ids.iter().find(|id| !todos.iter().any(|t| t.id == **id)) {
+        return Err(format!("no todo with id {id}").into());
+    }
+    todos.retain(|t| !ids.contains(&t.id));
+    Ok(save_todos(&todos)?)
+}
+
+fn edit(args: EditArgs) -> Result<(), Error> {
+    let mut todos = load_todos()?;
+    let workspaces = load_workspaces()?;
+    let todo = find_todo(&mut todos, args.id)?;
+
+    let no_options = args.text.is_none()
+        && args.due.is_none()
+        && args.tags.is_empty()
+        && args.untags.is_empty()
+        && args.workspace.is_none()
+        && args.link.is_none();
+    let text = match (args.text, no_options) {
+        (Some(text), _) => Some(text),
+        (None, true) => Some(edit_in_editor(&todo.text)?),
+        (None, false) => None,
+    };
+
+    if let Some(text) = text {
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            return Err(Error::Usage(String::from("the todo text is empty")));
+        }
+        // A link found in the old text follows the text.
+        if todo.link.is_none() || todo.link == todo::find_link(&todo.text) {
+            todo.link = todo::find_link(&text);
+        }
+        todo.text = text;
+    }
+    match args.link.as_deref() {
+        Some("none") => todo.link = None,
+        Some(link) => todo.link = Some(parse_link(link)?),
+        None => {}
+    }
+    match args.due.as_deref() {
+        Some("none") => todo.due = None,
+        Some(date) => todo.due = Some(parse_due(date)?),
+        None => {}
+    }
+    match args.workspace.as_deref() {
+        Some("none") => todo.workspace = None,
+        Some(name) => todo.workspace = Some(find_workspace(&workspaces, name)?.id),
+        None => {}
+    }
+    for tag in &args.tags {
+        add_tag(todo, tag);
+    }
+    let untags: Vec<String> = args.untags.iter().map(|t| todo::normalize_tag(t)).collect();
+    todo.tags.retain(|t| !untags.contains(t));
+
+    Ok(save_todos(&todos)?)
+}
+
+fn open(id: usize) -> Result<(), Error> {
+    let mut todos = load_todos()?;
+    let todo = find_todo(&mut todos, id)?;
+    let link = todo
+        .link
+        .as_ref()
+        .ok_or_else(|| format!("todo {id} has no link"))?;
+    let config = Config::load().map_err(|err| err.to_string())?;
+    Ok(editor::open(config.editor.as_deref(), link)?)
+}
+
+/// Lets the user change `text` in their editor.
+fn edit_in_editor(text: &str) -> Result<String, String> {
+    let editor = editor::from_env();
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    let path = env::temp_dir().join(format!("lazytodo-{}.txt", process::id()));
+    fs::write(&path, format!("{text}\n")).map_err(|err| format!("{}: {err}", path.display()))?;
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .map_err(|err| format!("{program}: {err}"));
+    let edited = fs::read_to_string(&path).map_err(|err| format!("{}: {err}", path.display()));
+    let _ = fs::remove_file(&path);
+
+    match status? {
+        status if status.success() => edited,
+        status => Err(format!("{program} exited with {status}")),
+    }
+}
+
+fn add_workspace(title: String, parent: Option<String>) -> Result<(), Error> {
+    let title = title.trim().to_string();
+    if title.is_empty() {
+        return Err(Error::Usage(String::from("the workspace title is empty")));
+    }
+    let mut workspaces = load_workspaces()?;
+    if workspaces
+        .iter()
+        .any(|w| w.title.eq_ignore_ascii_case(&title))
+    {
+        return Err(format!("a workspace named `{title}` already exists").into());
+    }
+
+    let mut new = Workspace::new(workspace::next_id(&workspaces), title);
+    new.parent = match parent {
+        Some(name) => Some(find_workspace(&workspaces, &name)?.id),
+        None => None,
+    };
+    let id = new.id;
+    workspaces.push(new);
+    save_workspaces(&workspaces)?;
+    println!("{id}");
+    Ok(())
+}
+
+fn list_workspaces() -> Result<(), Error> {
+    let todos = load_todos()?;
+    let workspaces = load_workspaces()?;
+
+    let rows: Vec<[String; 5]> = workspace::all_rows(&workspaces)
+        .iter()
+        .map(|row| {
+            let workspace = &workspaces[row.index];
+            let (open, done) = stats::subtree_counts(&todos, &workspaces, workspace.id);
+            [
+                workspace.id.to_string(),
+                format!("{}{}", "  ".repeat(row.depth), workspace.title),
+                open.to_string(),
+                done.to_string(),
+                workspace.file.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print_table(["ID", "WORKSPACE", "OPEN", "DONE", "FILE"], &rows);
+    Ok(())
+}
+
+fn remove_workspace(name: &str) -> Result<(), Error> {
+    let mut todos = load_todos()?;
+    let mut workspaces = load_workspaces()?;
+    let (id, parent) = {
+        let workspace = find_workspace(&workspaces, name)?;
+        (workspace.id, workspace.parent)
+    };
+
+    workspace::remove(&mut workspaces, id);
+    let moved = todos
+        .iter_mut()
+        .filter(|t| t.workspace == Some(id))
+        .map(|t| t.workspace = parent)
+        .count();
+    if moved > 0 {
+        save_todos(&todos)?;
+    }
+    Ok(save_workspaces(&workspaces)?)
+}
+
+fn bind_workspace(name: &str, file: Option<String>) -> Result<(), Error> {
+    let mut workspaces = load_workspaces()?;
+    let id = find_workspace(&workspaces, name)?.id;
+    if let Some(file) = &file {
+        if let Some(other) = workspaces
+            .iter()
+            .find(|w| w.id != id && w.file.as_ref() == Some(file))
+        {
+            return Err(format!("{file} is already bound to `{}`", other.title).into());
+        }
+    }
+    if let Some(workspace) = workspaces.iter_mut().find(|w| w.id == id) {
+        workspace.file = file.clone();
+    }
+    save_workspaces(&workspaces)?;
+
+    if file.is_some() {
+        // The items already in the file join the todos of the workspace, nothing is deleted.
+        merge_files(false)?;
+        save_todos(&load_todos()?)?;
+    }
+    Ok(())
+}
+
+fn export(format: ExportFormat, output: Option<PathBuf>, columns: &[Column]) -> Result<(), Error> {
+    let todos = load_todos()?;
+    let archive = load_archive(&Location::default())?;
+    let workspaces = load_workspaces()?;
+
+    let contents = export_contents(format, &todos, &archive, &workspaces, columns)?;
+    match output {
+        Some(path) => {
+            fs::write(&path, contents).map_err(|err| format!("{}: {err}", path.display()))?
+        }
+        None => print!("{contents}"),
+    }
+    Ok(())
+}
+
+fn export_contents(
+    format: ExportFormat,
+    todos: &[Todo],
+    archive: &[Todo],
+    workspaces: &[Workspace],
+    columns: &[Column],
+) -> Result<String, String> {
+    Ok(match format {
+        ExportFormat::Json => {
+            json::export(todos, archive, workspaces).map_err(|err| err.to_string())? + "\n"
+        }
+        ExportFormat::Jsonl => json::export_lines(todos).map_err(|err| err.to_string())?,
+        // Archived todos belong in a separate done.txt.
+        ExportFormat::Todotxt => todotxt::export(todos, workspaces),
+        ExportFormat::Markdown => markdown::export(todos, workspaces),
+        // Lines end in CRLF as the format requires.
+        ExportFormat::Ics => ics::export(todos, Utc::now()),
+        ExportFormat::Org => org::export(todos, workspaces),
+        ExportFormat::Csv => csv::export(todos, workspaces, columns),
+    })
+}
+
+/// Writes the store to `path` in the format of its extension, for the `export` command
+/// of the palette. The archive is read from `location`.
+pub fn export_file(
+    path: &Path,
+    location: &Location,
+    todos: &[Todo],
+    workspaces: &[Workspace],
+) -> Result<(), String> {
+    let format = ExportFormat::from_path(path).ok_or_else(|| {
+        String::from("export to a .json, .jsonl, .txt, .md, .ics, .org or .csv file")
+    })?;
+    let archive = load_archive(location)?;
+    let contents = export_contents(format, todos, &archive, workspaces, &Column::ALL)?;
+    fs::write(path, contents).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn print_stats(by: StatsBy, format: StatsFormat) -> Result<(), Error> {
+    let mut todos = load_todos()?;
+    todos.extend(load_archive(&Location::default())?);
+
+    let (header, rows): ([&str; 3], Vec<[String; 3]>) = match by {
+        StatsBy::Day => (
+            ["date", "created", "completed"],
+            stats::per_day(&todos)
+                .into_iter()
+                .map(|day| {
+                    [
+                        day.date.format("%Y-%m-%d").to_string(),
+                        day.created.to_string(),
+                        day.completed.to_string(),
+                    ]
+                })
+                .collect(),
+        ),
+        StatsBy::Workspace => (
+            ["workspace", "open", "done"],
+            stats::per_workspace(&todos, &load_workspaces()?)
+                .into_iter()
+                .map(|counts| {
+                    [
+                        counts.path.join("/"),
+                        counts.open.to_string(),
+                        counts.done.to_string(),
+                    ]
+                })
+                .collect(),
+        ),
+    };
+
+    match format {
+        StatsFormat::Table => print_table(
+            header.map(str::to_uppercase).each_ref().map(String::as_str),
+            &rows,
+        ),
+        StatsFormat::Csv => {
+            let mut out = String::new();
+            csv::push_row(&mut out, header);
+            for row in &rows {
+                csv::push_row(&mut out, row.iter().map(String::as_str));
+            }
+            print!("{out}");
+        }
+    }
+    Ok(())
+}
+
+fn import(
+    format: ImportFormat,
+    file: Option<PathBuf>,
+    skip_existing: bool,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let input = match file.filter(|path| path.as_os_str() != "-") {
+        Some(path) => {
+            fs::read_to_string(&path).map_err(|err| format!("{}: {err}", path.display()))?
+        }
+        None => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|err| format!("stdin: {err}"))?;
+            input
+        }
+    };
+    let mut todos = load_todos()?;
+    let mut workspaces = load_workspaces()?;
+    let workspace_count = workspaces.len();
+    let invalid = |errors: Vec<String>| format!("invalid input:\n  {}", errors.join("\n  "));
+
+    let summary = match format {
+        ImportFormat::Jsonl => {
+            let count = json::import_lines(&input, &mut todos, &workspaces).map_err(invalid)?;
+            format!("{count} todos")
+        }
+        ImportFormat::Todotxt => {
+            let count = todotxt::import(&input, &mut todos, &mut workspaces);
+            format!("{count} todos")
+        }
+        ImportFormat::Ics => {
+            let summary = ics::import(&input, &mut todos, skip_existing).map_err(invalid)?;
+            format!(
+                "{} todos, updated {}, skipped {}",
+                summary.added, summary.updated, summary.skipped
+            )
+        }
+        ImportFormat::Org => {
+            let count = org::import(&input, &mut todos, &mut workspaces).map_err(invalid)?;
+            format!("{count} todos")
+        }
+        ImportFormat::Taskwarrior => {
+            let report = taskwarrior::import(&input, &mut todos, &mut workspaces)?;
+            if dry_run {
+                print_taskwarrior_report(&report, &todos, &workspaces);
+            }
+            format!(
+                "{} todos and {} workspaces, skipped {} deleted, {} recurring and {} \
+                 already imported tasks",
+                report.todos.len(),
+                report.workspaces.len(),
+                report.deleted,
+                report.recurring,
+                report.existing
+            )
+        }
+    };
+
+    if dry_run {
+        println!("Would import {summary}");
+        return Ok(());
+    }
+    if workspaces.len() > workspace_count {
+        save_workspaces(&workspaces)?;
+    }
+    save_todos(&todos)?;
+    println!("Imported {summary}");
+    Ok(())
+}
+
+/// The workspaces and todos a Taskwarrior import would create.
+fn print_taskwarrior_report(
+    report: &taskwarrior::Report,
+    todos: &[Todo],
+    workspaces: &[Workspace],
+) {
+    for title in &report.workspaces {
+        println!("workspace  {title}");
+    }
+    for todo in todos.iter().filter(|t| report.todos.contains(&t.id)) {
+        let mut line = markdown::item_line(todo);
+        if let Some(workspace) = todo
+            .workspace
+            .and_then(|id| workspaces.iter().find(|w| w.id == id))
+        {
+            line.push_str(&format!(" @{}", workspace.title));
+        }
+        println!("todo       {line}");
+    }
+}
+
+fn list_code_todos(format: Format) -> Result<(), Error> {
+    let todos = load_todos()?;
+    let code_todos = scanner::scan(Path::new("."));
+
+    match format {
+        Format::Json => {
+            let records: Vec<serde_json::Value> = code_todos
+                .iter()
+                .map(|code_todo| {
+                    serde_json::json!({
+                        "keyword": code_todo.keyword,
+                        "text": code_todo.text,
+                        "path": code_todo.link.path,
+                        "line": code_todo.link.line,
+                        "todo": code_todo.promoted(&todos).map(|t| t.id),
+                    })
+                })
+                .collect();
+            let json = serde_json::to_string_pretty(&records).map_err(|err| err.to_string())?;
+            println!("{json}");
+        }
+        Format::Plain => {
+            for code_todo in &code_todos {
+                println!("{}\t{}", code_todo.link, code_todo.text);
+            }
+        }
+        Format::Table => {
+            let rows: Vec<[String; 4]> = code_todos
+                .iter()
+                .map(|code_todo| {
+                    let promoted = code_todo.promoted(&todos).map(|t| t.id.to_string());
+                    [
+                        code_todo.link.to_string(),
+                        code_todo.keyword.to_string(),
+                        promoted.unwrap_or_default(),
+                        code_todo.text.clone(),
+                    ]
+                })
+                .collect();
+            print_table(["LOCATION", "KIND", "TODO", "COMMENT"], &rows);
+        }
+    }
+    Ok(())
+}
+
+fn promote_code_todo(location: &str, workspace: Option<String>) -> Result<(), Error> {
+    let link: Link = location
+        .trim_start_matches("./")
+        .parse()
+        .map_err(|_| Error::Usage(format!("`{location}` is not a location")))?;
+    let code_todos = scanner::scan(Path::new("."));
+    let code_todo = code_todos
+        .iter()
+        .find(|c| c.link == link)
+        .ok_or_else(|| format!("no TODO, FIXME or HACK comment at {location}"))?;
+
+    let mut todos = load_todos()?;
+    if let Some(todo) = code_todo.promoted(&todos) {
+        return Err(format!("{location} is already todo {}", todo.id).into());
+    }
+    let mut todo = code_todo.promote(todo::next_id(&todos));
+    todo.workspace = match workspace {
+        Some(name) => Some(find_workspace(&load_workspaces()?, &name)?.id),
+        None => None,
+    };
+
+    let id = todo.id;
+    todos.push(todo);
+    save_todos(&todos)?;
+    println!("{id}");
+    Ok(())
+}
+
+fn seed_store(
+    dir: &Path,
+    workspaces: usize,
+    todos: usize,
+    seed: u64,
+    date: Option<String>,
+    force: bool,
+) -> Result<(), Error> {
+    let today = Local::now().date_naive();
+    let today = match date {
+        Some(date) => dates::parse_date(&date, today)
+            .ok_or_else(|| Error::Usage(format!("`{date}` is not a date")))?,
+        None => today,
+    };
+    let store_dir = dir.join(store::DIR);
+    if store_dir.exists() && !force {
+        return Err(format!(
+            "{} already exists, pass --force to replace its todos",
+            store_dir.display()
+        )
+        .into());
+    }
+    fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    env::set_current_dir(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    let location = Location::default();
+    let _lock =
+        store::lock(&location).map_err(|err| format!("{}: {err}", location.lock().display()))?;
+
+    let store = seed::generate(workspaces, todos, seed, today);
+    store
+        .save(&location)
+        .map_err(|err| format!("{}: {err}", store_dir.display()))?;
+    println!(
+        "{todos} todos in {workspaces} workspaces, run lazytodo in {} to see them",
+        dir.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use std::{collections::HashMap, env, fs, io, path::PathBuf};
+
+use color_eyre::{eyre::eyre, Result};
+use serde::Deserialize;
+
+use crate::{
+    keymap::{Action, Context, KeySequence, Keymap},
+    theme::{self, Theme, ThemeConfig},
+};
+
+/// Contents of `config.toml`, e.g.
+///
+/// ```toml
+/// confirm_quit = false
+/// theme = "mine"
+/// editor = "code -g {path}:{line}"
+///
+/// [themes.mine]
+/// base = "light"
+/// accent = "#d33682"
+///
+/// [keys.global]
+/// quit = ["q", "ctrl-c"]
+///
+/// [keys.todos]
+/// top = "g g"
+/// ```
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    confirm_quit: Option<bool>,
+    theme: Option<String>,
+    editor: Option<String>,
+    themes: HashMap<String, ThemeConfig>,
+    keys: HashMap<String, HashMap<String, Bindings>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Bindings {
+    One(String),
+    Many(Vec<String>),
+}
+
+pub struct Config {
+    pub confirm_quit: bool,
+    pub keymap: Keymap,
+    /// Built-in themes followed by the user defined ones.
+    pub themes: Vec<Theme>,
+    /// Index in `themes` of the theme to start with.
+    pub theme: usize,
+    /// Command opening linked files, see [`lazytodo::editor::command`].
+    pub editor: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            confirm_quit: true,
+            keymap: Keymap::default(),
+            themes: Theme::built_in(),
+            theme: 0,
+            editor: None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file, using the defaults when it does not exist.
+    pub fn load() -> Result<Self> {
+        let mut config = Self::load_file()?;
+        if theme::no_color() {
+            config.themes = vec![Theme::no_color()];
+            config.theme = 0;
+        }
+        Ok(config)
+    }
+
+    fn load_file() -> Result<Self> {
+        let Some(path) = config_dir().map(|dir| dir.join("config.toml")) else {
+            return Ok(Self::default());
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(eyre!("{}: {err}", path.display())),
+        };
+
+        Self::parse(&contents).map_err(|errors| {
+            eyre!(
+                "invalid config {}:\n  {}",
+                path.display(),
+                errors.join("\n  ")
+            )
+        })
+    }
+
+    fn parse(contents: &str) -> Result<Self, Vec<String>> {
+        let file: ConfigFile = toml::from_str(contents).map_err(|err| vec![err.to_string()])?;
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+
+        if let Some(confirm_quit) = file.confirm_quit {
+            config.confirm_quit = confirm_quit;
+        }
+        match file.editor {
+            Some(editor) if editor.trim().is_empty() => errors.push(String::from("empty editor")),
+            editor => config.editor = editor,
+        }
+
+        // Sorted so that a theme can build on any theme whose name comes before it.
+        let mut built: HashMap<String, Theme> = config
+            .themes
+            .iter()
+            .map(|theme| (theme.name.clone(), theme.clone()))
+            .collect();
+        let mut user_themes: Vec<_> = file.themes.into_iter().collect();
+        user_themes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (name, theme_config) in user_themes {
+            match theme_config.build(&name, &built) {
+                Ok(theme) => {
+                    config.themes.retain(|t| t.name != name);
+                    config.themes.push(theme.clone());
+                    built.insert(name, theme);
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+        if let Some(name) = file.theme {
+            match config.themes.iter().position(|t| t.name == name) {
+                Some(idx) => config.theme = idx,
+                None => errors.push(format!("unknown theme `{name}`")),
+            }
+        }
+
+        for (context_name, bindings) in file.keys {
+            let Some(context) = Context::from_name(&context_name) else {
+                errors.push(format!("[keys.{context_name}] unknown context"));
+                continue;
+            };
+            for (action_name, keys) in bindings {
+                let Some(action) = Action::from_name(&action_name) else {
+                    errors.push(format!(
+                        "[keys.{context_name}] unknown action `{action_name}`"
+                    ));
+                    continue;
+                };
+                let keys = match keys {
+                    Bindings::One(keys) => vec![keys],
+                    Bindings::Many(keys) => keys,
+                };
+                match keys
+                    .iter()
+                    .map(|keys| keys.parse::<KeySequence>())
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(keys) => config.keymap.rebind(context, action, keys),
+                    Err(err) => errors.push(format!("[keys.{context_name}] {action_name}: {err}")),
+                }
+            }
+        }
+
+        errors.extend(config.keymap.validate());
+        match errors.is_empty() {
+            true => Ok(config),
+            false => Err(errors),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/lazytodo`, falling back to `~/.config/lazytodo`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("lazytodo"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(contents: &str) -> Vec<String> {
+        Config::parse(contents).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn rebinds_keys() {
+        let config = Config::parse(
+            r#"
+            confirm_quit = false
+
+            [keys.global]
+            quit = ["q", "ctrl-c"]
+
+            [keys.todos]
+            top = "ctrl-home"
+            "#,
+        )
+        .unwrap();
+        assert!(!config.confirm_quit);
+        let quit: Vec<String> = config
+            .keymap
+            .keys_for(&Action::Quit)
+            .iter()
+            .map(|keys| keys.to_string())
+            .collect();
+        assert_eq!(quit, ["q", "ctrl-c"]);
+        assert!(config
+            .keymap
+            .keys_for(&Action::Top)
+            .iter()
+            .any(|keys| keys.to_string() == "ctrl-home"));
+    }
+
+    #[test]
+    fn reports_every_invalid_binding() {
+        let errors = errors(
+            r#"
+            [keys.todos]
+            add_todo = "hyper-a"
+            fly = "f"
+
+            [keys.nowhere]
+            quit = "q"
+            "#,
+        );
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors.contains(&String::from(
+            "[keys.todos] add_todo: unknown modifier `hyper` in `hyper-a`"
+        )));
+        assert!(errors.contains(&String::from("[keys.todos] unknown action `fly`")));
+        assert!(errors.contains(&String::from("[keys.nowhere] unknown context")));
+    }
+
+    #[test]
+    fn reports_conflicting_bindings() {
+        assert_eq!(
+            errors("[keys.todos]\nadd_todo = \"d\""),
+            ["[keys.todos] `d` is bound to both `delete_todo` and `add_todo`"]
+        );
+        let errors = errors("[keys.workspaces]\nnew_workspace = [\"n\", \"g\"]");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("overlap"), "{errors:?}");
+    }
+
+    #[test]
+    fn rejects_unknown_settings() {
+        assert_eq!(errors("confirm = true").len(), 1);
+        assert_eq!(errors("editor = \" \""), ["empty editor"]);
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, TimeZone, Weekday};
+
+const WEEKDAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "monday"),
+    (Weekday::Tue, "tuesday"),
+    (Weekday::Wed, "wednesday"),
+    (Weekday::Thu, "thursday"),
+    (Weekday::Fri, "friday"),
+    (Weekday::Sat, "saturday"),
+    (Weekday::Sun, "sunday"),
+];
+
+/// Parses a due date relative to `today`.
+///
+/// Accepts `today`, `tomorrow`, weekday names (`fri`, `friday`, always the next
+/// occurrence after today), offsets like `+3d` or `+2w`, and `YYYY-MM-DD`.
+pub fn parse_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.checked_add_days(Days::new(1)),
+        _ => {}
+    }
+
+    if let Some((weekday, _)) = WEEKDAYS
+        .iter()
+        .find(|(_, name)| input.len() >= 3 && name.starts_with(&input))
+    {
+        let ahead =
+            (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        let ahead = if ahead == 0 { 7 } else { ahead };
+        return today.checked_add_days(Days::new(ahead.into()));
+    }
+
+    if let Some(offset) = input.strip_prefix('+') {
+        let (amount, unit) = offset.split_at(offset.len().saturating_sub(1));
+        let amount: u64 = amount.parse().ok()?;
+        let days = match unit {
+            "d" => amount,
+            "w" => amount.checked_mul(7)?,
+            _ => return None,
+        };
+        return today.checked_add_days(Days::new(days));
+    }
+
+    NaiveDate::parse_from_str(&input, "%Y-%m-%d").ok()
+}
+
+/// Milliseconds timestamp of the local midnight starting `date`, as stored in todos.
+pub fn to_timestamp(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|datetime| Local.from_local_datetime(&datetime).earliest())
+        .map(|datetime| datetime.timestamp_millis())
+        .unwrap_or_default()
+}
+
+pub fn from_timestamp(millis: i64) -> Option<NaiveDate> {
+    DateTime::from_timestamp_millis(millis).map(|d| d.with_timezone(&Local).date_naive())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_relative_dates() {
+        // A Friday.
+        let today = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+        let date = |input| parse_date(input, today).map(|d| d.to_string());
+        assert_eq!(date("tomorrow").as_deref(), Some("2025-03-15"));
+        assert_eq!(date("fri").as_deref(), Some("2025-03-21"));
+        assert_eq!(date("Monday").as_deref(), Some("2025-03-17"));
+        assert_eq!(date("+3d").as_deref(), Some("2025-03-17"));
+        assert_eq!(date("+2w").as_deref(), Some("2025-03-28"));
+        assert_eq!(date("2025-12-31").as_deref(), Some("2025-12-31"));
+        assert_eq!(date("fr"), None);
+        assert_eq!(date("+3m"), None);
+    }
+
+    #[test]
+    fn rejects_offsets_out_of_range() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+        assert_eq!(parse_date("+3000000000000000000w", today), None);
+        assert_eq!(parse_date("+18446744073709551615d", today), None);
+        assert_eq!(parse_date("+99999999999999999999d", today), None);
+    }
+}
@@ -0,0 +1,135 @@
+use std::{env, path::Path, process};
+
+use crate::entities::todo::Link;
+
+/// Command templates of editors that do not take the usual `+line path`.
+///
+/// `{path}` and `{line}` are replaced in every word of a template, words that mention the
+/// line are dropped when the link has none.
+pub const PRESETS: &[(&str, &str)] = &[
+    ("vim", "vim +{line} {path}"),
+    ("nvim", "nvim +{line} {path}"),
+    ("helix", "hx {path}:{line}"),
+    ("hx", "hx {path}:{line}"),
+    ("code", "code -g {path}:{line}"),
+    ("emacs", "emacs +{line} {path}"),
+    ("nano", "nano +{line} {path}"),
+];
+
+/// The command opening `link`, from the `editor` setting or else `$VISUAL` and `$EDITOR`.
+///
+/// The setting is a preset name or a template such as `subl {path}:{line}`. Editors from
+/// the environment use their preset when they have one and `+line path` otherwise.
+pub fn command(setting: Option<&str>, link: &Link) -> Vec<String> {
+    let template = match setting {
+        Some(setting) => preset(setting).unwrap_or(setting).to_string(),
+        None => {
+            let editor = from_env();
+            let program = editor.split_whitespace().next().unwrap_or("vi");
+            let name = Path::new(program)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(program);
+            match preset(name) {
+                // Keeps the flags and the path of the program given in the environment.
+                Some(preset) => preset.replacen(name, &editor, 1),
+                None => format!("{editor} +{{line}} {{path}}"),
+            }
+        }
+    };
+
+    let line = link.line.map(|line| line.to_string());
+    template
+        .split_whitespace()
+        .filter_map(|word| match (&line, word.contains("{line}")) {
+            (Some(line), true) => Some(word.replace("{line}", line)),
+            (None, true) if word.contains("{path}") => {
+                Some(word.replace(":{line}", "").replace("{line}", ""))
+            }
+            (None, true) => None,
+            _ => Some(word.to_string()),
+        })
+        .map(|word| word.replace("{path}", &link.path))
+        .collect()
+}
+
+/// `$VISUAL` or `$EDITOR`, falling back to `vi`.
+pub fn from_env() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| String::from("vi"))
+}
+
+fn preset(name: &str) -> Option<&'static str> {
+    PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, template)| *template)
+}
+
+/// Runs the editor on `link` and waits for it to exit.
+pub fn open(setting: Option<&str>, link: &Link) -> Result<(), String> {
+    let command = command(setting, link);
+    let Some((program, args)) = command.split_first() else {
+        return Err(String::from("the editor command is empty"));
+    };
+    let status = process::Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|err| format!("{program}: {err}"))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(format!("{program} exited with {status}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(path: &str, line: Option<usize>) -> Link {
+        Link {
+            path: path.to_string(),
+            line,
+        }
+    }
+
+    #[test]
+    fn fills_presets_and_templates() {
+        let main = link("src/main.rs", Some(412));
+        assert_eq!(
+            command(Some("nvim"), &main),
+            ["nvim", "+412", "src/main.rs"]
+        );
+        assert_eq!(command(Some("helix"), &main), ["hx", "src/main.rs:412"]);
+        assert_eq!(
+            command(Some("code"), &main),
+            ["code", "-g", "src/main.rs:412"]
+        );
+        assert_eq!(
+            command(Some("subl -w {path}:{line}"), &main),
+            ["subl", "-w", "src/main.rs:412"]
+        );
+        assert_eq!(command(Some("open {path}"), &main), ["open", "src/main.rs"]);
+    }
+
+    #[test]
+    fn drops_the_line_when_the_link_has_none() {
+        let notes = link("docs/my notes.md", None);
+        assert_eq!(command(Some("vim"), &notes), ["vim", "docs/my notes.md"]);
+        assert_eq!(
+            command(Some("code"), &notes),
+            ["code", "-g", "docs/my notes.md"]
+        );
+        assert_eq!(
+            command(Some("kak {path} +{line}"), &notes),
+            ["kak", "docs/my notes.md"]
+        );
+        assert_eq!(
+            command(Some("edit --at={path}@{line}"), &notes),
+            ["edit", "--at=docs/my notes.md@"]
+        );
+    }
+}
@@ -1,4 +1,5 @@
+pub mod todo;
 pub mod workspace;
 
+pub use todo::Todo;
 pub use workspace::Workspace;
-
@@ -0,0 +1,238 @@
+use std::{fmt, str::FromStr};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Todo {
+    /// Stable number used to refer to the todo from the command line.
+    pub id: usize,
+    pub done: bool,
+    pub text: String,
+    pub created_at: i64,
+    pub workspace: Option<usize>,
+    pub due: Option<i64>,
+    pub tags: Vec<String>,
+    /// `A` (highest) to `Z`.
+    pub priority: Option<char>,
+    pub completed_at: Option<i64>,
+    /// `key:value` pairs without a meaning in lazytodo, kept for other tools.
+    pub fields: Vec<(String, String)>,
+    pub link: Option<Link>,
+    /// Free text kept with the todo, one note per line.
+    pub notes: String,
+}
+
+/// A location in a file, written `path:line`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Link {
+    pub path: String,
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}", self.path),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+impl FromStr for Link {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(String::from("empty link"));
+        }
+        let (path, line) = match s.rsplit_once(':') {
+            Some((path, line)) if !path.is_empty() && line.parse::<usize>().is_ok() => {
+                (path, line.parse().ok())
+            }
+            _ => (s, None),
+        };
+        Ok(Self {
+            path: path.to_string(),
+            line,
+        })
+    }
+}
+
+impl Todo {
+    pub fn new(id: usize, text: String) -> Self {
+        Self {
+            id,
+            done: false,
+            text,
+            created_at: Local::now().timestamp_millis(),
+            workspace: None,
+            due: None,
+            tags: Vec::new(),
+            priority: None,
+            completed_at: None,
+            fields: Vec::new(),
+            link: None,
+            notes: String::new(),
+        }
+    }
+
+    /// Marks the todo done or open, a todo already done keeps its completion time.
+    pub fn set_done(&mut self, done: bool) {
+        if self.done == done {
+            return;
+        }
+        self.done = done;
+        self.completed_at = match done {
+            true => Some(Local::now().timestamp_millis()),
+            false => None,
+        };
+    }
+
+    /// The text with the priority in front and the tags after it, e.g. `(A) Deploy #ops`.
+    pub fn display_text(&self) -> String {
+        let mut text = match self.priority {
+            Some(priority) => format!("({priority}) {}", self.text),
+            None => self.text.clone(),
+        };
+        for tag in &self.tags {
+            text.push_str(" #");
+            text.push_str(tag);
+        }
+        text
+    }
+
+    pub const fn ref_array(&self) -> (&bool, &String, &i64) {
+        (&self.done, &self.text, &self.created_at)
+    }
+}
+
+/// The id to give to a new todo.
+pub fn next_id(list: &[Todo]) -> usize {
+    list.iter().map(|t| t.id + 1).max().unwrap_or(0)
+}
+
+/// The first `path:line` in a todo text, e.g. `src/main.rs:412` in "Fix the crash in src/main.rs:412".
+///
+/// Only words that look like a file, with a `/` or an extension, are taken as a path. A
+/// column after the line, as in `src/main.rs:412:8`, is ignored.
+pub fn find_link(text: &str) -> Option<Link> {
+    text.split_whitespace().find_map(|word| {
+        let word = word
+            .trim_start_matches(|c: char| "`'\"([<".contains(c))
+            .trim_end_matches(|c: char| "`'\")]>,;.".contains(c));
+        let (rest, line) = word.rsplit_once(':')?;
+        let mut line: usize = line.parse().ok()?;
+        let path = match rest.rsplit_once(':') {
+            Some((path, column)) if column.parse::<usize>().is_ok() => {
+                line = column.parse().ok()?;
+                path
+            }
+            _ => rest,
+        };
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let looks_like_file = path.contains('/')
+            || file_name.rsplit_once('.').is_some_and(|(name, extension)| {
+                !name.is_empty() && extension.chars().any(|c| c.is_ascii_alphabetic())
+            });
+        (looks_like_file && !path.contains("://") && line > 0).then(|| Link {
+            path: path.to_string(),
+            line: Some(line),
+        })
+    })
+}
+
+/// Normalises a tag typed by the user, `#Ops` and `ops` are the same tag.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// Shape of a todo as stored before the files had a header.
+#[derive(Deserialize)]
+pub struct LegacyTodo {
+    done: bool,
+    text: String,
+    created_at: i64,
+}
+
+impl LegacyTodo {
+    pub fn into_todo(self, id: usize) -> Todo {
+        let mut todo = Todo::new(id, self.text);
+        todo.done = self.done;
+        todo.created_at = self.created_at;
+        todo
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(path: &str, line: usize) -> Option<Link> {
+        Some(Link {
+            path: path.to_string(),
+            line: Some(line),
+        })
+    }
+
+    #[test]
+    fn finds_links_in_text() {
+        assert_eq!(
+            find_link("Fix the crash in src/main.rs:412"),
+            link("src/main.rs", 412)
+        );
+        assert_eq!(
+            find_link("See (lib.rs:7), then `src/app.ts:12:8`."),
+            link("lib.rs", 7)
+        );
+        assert_eq!(
+            find_link("Column ignored src/app.ts:12:8"),
+            link("src/app.ts", 12)
+        );
+        assert_eq!(find_link("In docs/Makefile:3"), link("docs/Makefile", 3));
+    }
+
+    #[test]
+    fn ignores_words_that_are_not_files() {
+        for text in [
+            "Standup at 10:30",
+            "Read https://example.com:8080",
+            "Ratio 16:9 and v1.2:3",
+            "Empty line src/main.rs:0",
+            "No line src/main.rs:",
+            "Hidden .env:3",
+        ] {
+            assert_eq!(find_link(text), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn completing_twice_keeps_the_completion_time() {
+        let mut todo = Todo::new(0, String::from("Deploy"));
+        todo.set_done(true);
+        todo.completed_at = Some(1_000);
+        todo.set_done(true);
+        assert_eq!(todo.completed_at, Some(1_000));
+        todo.set_done(false);
+        assert_eq!(todo.completed_at, None);
+        todo.set_done(false);
+        assert!(!todo.done);
+    }
+
+    #[test]
+    fn parses_links() {
+        assert_eq!(
+            "src/main.rs:412".parse(),
+            Ok(link("src/main.rs", 412).unwrap())
+        );
+        assert_eq!(
+            " README.md ".parse(),
+            Ok(Link {
+                path: String::from("README.md"),
+                line: None
+            })
+        );
+        assert_eq!("".parse::<Link>(), Err(String::from("empty link")));
+    }
+}
@@ -1,14 +1,216 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Workspace {
     pub id: usize,
     pub title: String,
+    /// Index into `PALETTES`.
+    pub color: usize,
+    pub parent: Option<usize>,
+    pub collapsed: bool,
+    /// Markdown checklist kept in sync with the todos of the workspace.
+    pub file: Option<String>,
+}
+
+/// A workspace as it appears in the drawer tree.
+pub struct TreeRow {
+    /// Position of the workspace in the flat list.
+    pub index: usize,
+    pub depth: usize,
+    pub has_children: bool,
 }
 
 impl Workspace {
-    pub fn new(title: String) -> Self {
-        Self { id: 10, title }
+    pub fn new(id: usize, title: String) -> Self {
+        Self {
+            id,
+            title,
+            color: 0,
+            parent: None,
+            collapsed: false,
+            file: None,
+        }
+    }
+}
+
+/// Flattens the hierarchy in list order, skipping the children of collapsed workspaces.
+pub fn tree_rows(list: &[Workspace]) -> Vec<TreeRow> {
+    flatten(list, false)
+}
+
+/// Flattens the whole hierarchy, collapsing only matters in the interface.
+pub fn all_rows(list: &[Workspace]) -> Vec<TreeRow> {
+    flatten(list, true)
+}
+
+fn flatten(list: &[Workspace], expand: bool) -> Vec<TreeRow> {
+    let parents: Vec<Option<usize>> = list.iter().map(|w| tree_parent(list, w)).collect();
+    let mut rows = Vec::new();
+    for index in (0..list.len()).filter(|idx| parents[*idx].is_none()) {
+        push_rows(list, &parents, expand, index, 0, &mut rows);
+    }
+    rows
+}
+
+/// The parent under which a workspace is shown, `None` for the top level.
+///
+/// Workspaces whose parent was deleted, and those in a parent cycle of a hand-edited
+/// store, are shown at the top level.
+fn tree_parent(list: &[Workspace], workspace: &Workspace) -> Option<usize> {
+    let parent = workspace.parent?;
+    let mut ancestor = list.iter().find(|w| w.id == parent)?;
+    for _ in 0..list.len() {
+        match ancestor.parent {
+            Some(id) if id == workspace.id => return None,
+            Some(id) => match list.iter().find(|w| w.id == id) {
+                Some(next) => ancestor = next,
+                None => break,
+            },
+            None => break,
+        }
+    }
+    Some(parent)
+}
+
+fn push_rows(
+    list: &[Workspace],
+    parents: &[Option<usize>],
+    expand: bool,
+    index: usize,
+    depth: usize,
+    rows: &mut Vec<TreeRow>,
+) {
+    let workspace = &list[index];
+    let children: Vec<usize> = (0..list.len())
+        .filter(|idx| parents[*idx] == Some(workspace.id))
+        .collect();
+
+    rows.push(TreeRow {
+        index,
+        depth,
+        has_children: !children.is_empty(),
+    });
+
+    if workspace.collapsed && !expand {
+        return;
+    }
+    for child in children {
+        push_rows(list, parents, expand, child, depth + 1, rows);
+    }
+}
+
+/// Ids of the workspace `id` and all of its sub-workspaces.
+pub fn descendants(list: &[Workspace], id: usize) -> Vec<usize> {
+    let mut ids = vec![id];
+    let mut next = 0;
+    while next < ids.len() {
+        let parent = ids[next];
+        for workspace in list.iter().filter(|w| w.parent == Some(parent)) {
+            if !ids.contains(&workspace.id) {
+                ids.push(workspace.id);
+            }
+        }
+        next += 1;
     }
+    ids
 }
 
+/// Finds a workspace by title, ignoring case, or by id.
+pub fn find<'a>(list: &'a [Workspace], name: &str) -> Option<&'a Workspace> {
+    list.iter()
+        .find(|w| w.title.eq_ignore_ascii_case(name.trim()))
+        .or_else(|| {
+            let id: usize = name.trim().parse().ok()?;
+            list.iter().find(|w| w.id == id)
+        })
+}
+
+/// The id of the child of `parent` titled `title`, ignoring case, added when missing.
+///
+/// The flag tells whether the workspace was added.
+pub fn find_or_add_child(
+    list: &mut Vec<Workspace>,
+    parent: Option<usize>,
+    title: &str,
+) -> (usize, bool) {
+    if let Some(workspace) = list
+        .iter()
+        .find(|w| w.parent == parent && w.title.eq_ignore_ascii_case(title))
+    {
+        return (workspace.id, false);
+    }
+    let mut workspace = Workspace::new(next_id(list), title.to_string());
+    workspace.parent = parent;
+    let id = workspace.id;
+    list.push(workspace);
+    (id, true)
+}
+
+/// Removes the workspace `id`, moving its sub-workspaces up one level.
+pub fn remove(list: &mut Vec<Workspace>, id: usize) -> Option<Workspace> {
+    let idx = list.iter().position(|w| w.id == id)?;
+    let removed = list.remove(idx);
+    for workspace in list.iter_mut() {
+        if workspace.parent == Some(removed.id) {
+            workspace.parent = removed.parent;
+        }
+    }
+    Some(removed)
+}
+
+pub fn next_id(list: &[Workspace]) -> usize {
+    list.iter().map(|w| w.id + 1).max().unwrap_or(0)
+}
+
+/// Shape of a workspace as stored before the files had a header.
+#[derive(Deserialize)]
+pub struct LegacyWorkspace {
+    pub id: usize,
+    pub title: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(id: usize, parent: Option<usize>) -> Workspace {
+        let mut workspace = Workspace::new(id, format!("w{id}"));
+        workspace.parent = parent;
+        workspace
+    }
+
+    #[test]
+    fn parent_cycles_are_shown_at_the_top_level() {
+        // 1 and 2 are each other's parent, 3 hangs below the cycle.
+        let list = [
+            workspace(0, None),
+            workspace(1, Some(2)),
+            workspace(2, Some(1)),
+            workspace(3, Some(2)),
+            workspace(4, Some(0)),
+        ];
+        let rows: Vec<(usize, usize)> = tree_rows(&list)
+            .iter()
+            .map(|row| (list[row.index].id, row.depth))
+            .collect();
+        assert_eq!(rows, vec![(0, 0), (4, 1), (1, 0), (2, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn all_rows_include_collapsed_children() {
+        let mut list = [
+            workspace(0, None),
+            workspace(1, Some(0)),
+            workspace(2, Some(1)),
+            workspace(3, None),
+        ];
+        list[1].collapsed = true;
+        let ids = |rows: Vec<TreeRow>| {
+            rows.iter()
+                .map(|row| list[row.index].id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(tree_rows(&list)), [0, 1, 3]);
+        assert_eq!(ids(all_rows(&list)), [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crossterm::event::{self, Event};
+use lazytodo::{
+    scanner::CodeTodo,
+    store::{self, Location},
+};
+
+/// How long the input thread waits for an event before checking whether it should stop.
+const POLL_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// How often the store files are checked for saves of other processes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What the app wakes up for: terminal input, the tick, or the result of a background task.
+pub enum Message {
+    Input(io::Result<Event>),
+    Tick,
+    /// The comments found by the scanner, which can take a while on large projects
+    CodeTodos(Vec<CodeTodo>),
+    /// The store files were saved, by this process or another one
+    StoreChanged,
+}
+
+/// The threads feeding the app's channel, read one message at a time by the event loop.
+///
+/// Blocking on the channel keeps the app asleep between messages.
+pub struct Events {
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    // Stop flag and handle of the thread reading the terminal, none while paused
+    input: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl Events {
+    pub fn new(tick_rate: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        let ticks = sender.clone();
+        thread::spawn(move || {
+            // Stops once the app dropped the receiver.
+            while ticks.send(Message::Tick).is_ok() {
+                thread::sleep(tick_rate);
+            }
+        });
+
+        let mut events = Self {
+            sender,
+            receiver,
+            input: None,
+        };
+        events.resume_input();
+        events
+    }
+
+    /// Waits for the next message.
+    pub fn next(&self) -> Message {
+        self.receiver
+            .recv()
+            .expect("the channel stays open while the events hold a sender")
+    }
+
+    /// The next message if one is already waiting.
+    pub fn try_next(&self) -> Option<Message> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Runs a task on its own thread, its result coming back as a message.
+    pub fn spawn(&self, task: impl FnOnce() -> Message + Send + 'static) {
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let _ = sender.send(task());
+        });
+    }
+
+    /// Sends [`Message::StoreChanged`] whenever the modification time of the store changes.
+    pub fn watch_store(&self, location: Location) {
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let mut modified = store::modified(&location);
+            loop {
+                thread::sleep(WATCH_INTERVAL);
+                let now = store::modified(&location);
+                if now != modified {
+                    modified = now;
+                    if sender.send(Message::StoreChanged).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Stops reading the terminal, so that another program can use it.
+    pub fn pause_input(&mut self) {
+        if let Some((stop, handle)) = self.input.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+        }
+    }
+
+    pub fn resume_input(&mut self) {
+        if self.input.is_some() {
+            return;
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let sender = self.sender.clone();
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    // Polling rather than blocking on `read` lets the thread notice it should stop.
+                    let event = match event::poll(POLL_TIMEOUT) {
+                        Ok(false) => continue,
+                        Ok(true) => event::read(),
+                        Err(err) => Err(err),
+                    };
+                    let failed = event.is_err();
+                    if sender.send(Message::Input(event)).is_err() || failed {
+                        break;
+                    }
+                }
+            }
+        });
+        self.input = Some((stop, handle));
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        // Leaves the terminal alone once the app restores it.
+        self.pause_input();
+    }
+}
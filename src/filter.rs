@@ -0,0 +1,87 @@
+use chrono::NaiveDate;
+
+use crate::{
+    dates,
+    entities::{todo, workspace, Todo, Workspace},
+};
+
+/// Whitespace separated terms that a todo must all match, e.g. `open #ops @infra due:fri deploy`.
+///
+/// - `done`, `open`
+/// - `#tag` or `tag:tag`
+/// - `@workspace` or `workspace:workspace`, sub-workspaces included
+/// - `due:none`, `due:overdue` or `due:<date>` for todos due on or before the date
+/// - anything else is searched in the text, ignoring case
+pub struct Filter {
+    terms: Vec<Term>,
+    today: NaiveDate,
+}
+
+enum Term {
+    Done(bool),
+    Tag(String),
+    Workspace(Vec<usize>),
+    NoDue,
+    Overdue,
+    DueBy(NaiveDate),
+    Text(String),
+}
+
+impl Filter {
+    pub fn parse(input: &str, workspaces: &[Workspace], today: NaiveDate) -> Result<Self, String> {
+        let terms = input
+            .split_whitespace()
+            .map(|term| Term::parse(term, workspaces, today))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { terms, today })
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        self.terms.iter().all(|term| match term {
+            Term::Done(done) => todo.done == *done,
+            Term::Tag(tag) => todo.tags.contains(tag),
+            Term::Workspace(ids) => todo.workspace.is_some_and(|w| ids.contains(&w)),
+            Term::NoDue => todo.due.is_none(),
+            Term::Overdue => !todo.done && due_date(todo).is_some_and(|due| due < self.today),
+            Term::DueBy(date) => due_date(todo).is_some_and(|due| due <= *date),
+            Term::Text(text) => todo.text.to_lowercase().contains(text),
+        })
+    }
+}
+
+impl Term {
+    fn parse(term: &str, workspaces: &[Workspace], today: NaiveDate) -> Result<Self, String> {
+        if let Some(tag) = term.strip_prefix('#').or_else(|| term.strip_prefix("tag:")) {
+            return Ok(Term::Tag(todo::normalize_tag(tag)));
+        }
+        if let Some(name) = term
+            .strip_prefix('@')
+            .or_else(|| term.strip_prefix("workspace:"))
+        {
+            let workspace = workspace::find(workspaces, name)
+                .ok_or_else(|| format!("no workspace named `{name}`"))?;
+            return Ok(Term::Workspace(workspace::descendants(
+                workspaces,
+                workspace.id,
+            )));
+        }
+        if let Some(due) = term.strip_prefix("due:") {
+            return match due {
+                "none" => Ok(Term::NoDue),
+                "overdue" => Ok(Term::Overdue),
+                _ => dates::parse_date(due, today)
+                    .map(Term::DueBy)
+                    .ok_or_else(|| format!("`{due}` is not a date")),
+            };
+        }
+        match term {
+            "done" => Ok(Term::Done(true)),
+            "open" => Ok(Term::Done(false)),
+            _ => Ok(Term::Text(term.to_lowercase())),
+        }
+    }
+}
+
+fn due_date(todo: &Todo) -> Option<NaiveDate> {
+    todo.due.and_then(dates::from_timestamp)
+}
@@ -0,0 +1,181 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    dates,
+    entities::{Todo, Workspace},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Id,
+    Workspace,
+    Text,
+    Done,
+    CreatedAt,
+    CompletedAt,
+    Due,
+    Tags,
+    Priority,
+}
+
+impl Column {
+    pub const ALL: [Column; 9] = [
+        Column::Id,
+        Column::Workspace,
+        Column::Text,
+        Column::Done,
+        Column::CreatedAt,
+        Column::CompletedAt,
+        Column::Due,
+        Column::Tags,
+        Column::Priority,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Workspace => "workspace",
+            Column::Text => "text",
+            Column::Done => "done",
+            Column::CreatedAt => "created_at",
+            Column::CompletedAt => "completed_at",
+            Column::Due => "due",
+            Column::Tags => "tags",
+            Column::Priority => "priority",
+        }
+    }
+
+    fn value(&self, todo: &Todo, workspaces: &[Workspace]) -> String {
+        let datetime = |millis: i64| {
+            DateTime::from_timestamp_millis(millis).map(|d| {
+                d.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+        };
+        match self {
+            Column::Id => todo.id.to_string(),
+            Column::Workspace => todo
+                .workspace
+                .and_then(|id| workspaces.iter().find(|w| w.id == id))
+                .map(|w| w.title.clone())
+                .unwrap_or_default(),
+            Column::Text => todo.text.clone(),
+            Column::Done => todo.done.to_string(),
+            Column::CreatedAt => datetime(todo.created_at).unwrap_or_default(),
+            Column::CompletedAt => todo.completed_at.and_then(datetime).unwrap_or_default(),
+            Column::Due => todo
+                .due
+                .and_then(dates::from_timestamp)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            Column::Tags => todo.tags.join(" "),
+            Column::Priority => todo.priority.map(String::from).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name() == s.trim())
+            .ok_or_else(|| {
+                format!(
+                    "unknown column `{s}`, expected one of {}",
+                    Column::ALL.map(|c| c.name()).join(", ")
+                )
+            })
+    }
+}
+
+/// Writes one CSV row per todo after a header row, quoted as in RFC 4180.
+///
+/// Dates are in local time, tags are separated by spaces.
+pub fn export(todos: &[Todo], workspaces: &[Workspace], columns: &[Column]) -> String {
+    let mut out = String::new();
+    push_row(&mut out, columns.iter().map(|column| column.name()));
+    for todo in todos {
+        let values: Vec<String> = columns
+            .iter()
+            .map(|column| column.value(todo, workspaces))
+            .collect();
+        push_row(&mut out, values.iter().map(String::as_str));
+    }
+    out
+}
+
+/// Appends a CRLF terminated row, quoting the fields that need it.
+pub fn push_row<'a>(out: &mut String, fields: impl IntoIterator<Item = &'a str>) {
+    for (idx, field) in fields.into_iter().enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(fields: &[&str]) -> String {
+        let mut out = String::new();
+        push_row(&mut out, fields.iter().copied());
+        out
+    }
+
+    #[test]
+    fn quotes_fields_as_in_rfc_4180() {
+        assert_eq!(row(&["plain", "", "with space"]), "plain,,with space\r\n");
+        assert_eq!(
+            row(&["a,b", "say \"hi\"", "two\nlines", "cr\r"]),
+            "\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\"cr\r\"\r\n"
+        );
+        assert_eq!(row(&["\""]), "\"\"\"\"\r\n");
+    }
+
+    #[test]
+    fn exports_the_chosen_columns() {
+        let workspaces = vec![Workspace::new(0, String::from("Home, garden"))];
+        let mut todo = Todo::new(4, String::from("Buy \"oat\" milk"));
+        todo.workspace = Some(0);
+        todo.tags = vec![String::from("shop"), String::from("food")];
+        todo.priority = Some('B');
+
+        let columns = [
+            Column::Id,
+            Column::Workspace,
+            Column::Text,
+            Column::Tags,
+            Column::Priority,
+        ];
+        assert_eq!(
+            export(&[todo], &workspaces, &columns),
+            "id,workspace,text,tags,priority\r\n\
+             4,\"Home, garden\",\"Buy \"\"oat\"\" milk\",shop food,B\r\n"
+        );
+        assert_eq!("due".parse(), Ok(Column::Due));
+        assert!("title"
+            .parse::<Column>()
+            .unwrap_err()
+            .starts_with("unknown column `title`"));
+    }
+}
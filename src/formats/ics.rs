@@ -0,0 +1,559 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::{
+    dates,
+    entities::{todo, Todo},
+};
+
+const DATE_FORMAT: &str = "%Y%m%d";
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+/// Lines longer than this many bytes are folded, see RFC 5545 section 3.1.
+const LINE_LENGTH: usize = 75;
+/// Todos exported without a `uid` field get `<id>@lazytodo`.
+const UID_DOMAIN: &str = "lazytodo";
+
+/// Writes the todos as the `VTODO`s of a calendar, see RFC 5545.
+///
+/// Tags become `CATEGORIES`, priorities `A` to `I` the `PRIORITY` 1 to 9 and a todo.txt
+/// `rec:` field an `RRULE`. `stamp` is the `DTSTAMP` of every component.
+pub fn export(todos: &[Todo], stamp: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//lazytodo//lazytodo//EN"),
+    ];
+    for todo in todos {
+        lines.push(String::from("BEGIN:VTODO"));
+        lines.push(format!("UID:{}", escape(&uid(todo))));
+        lines.push(format!("DTSTAMP:{}", utc(stamp)));
+        if let Some(created) = DateTime::from_timestamp_millis(todo.created_at) {
+            lines.push(format!("CREATED:{}", utc(created)));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&todo.text)));
+        if let Some(due) = todo.due.and_then(dates::from_timestamp) {
+            lines.push(format!("DUE;VALUE=DATE:{}", due.format(DATE_FORMAT)));
+        }
+        if todo.done {
+            lines.push(String::from("STATUS:COMPLETED"));
+            if let Some(completed) = todo.completed_at.and_then(DateTime::from_timestamp_millis) {
+                lines.push(format!("COMPLETED:{}", utc(completed)));
+            }
+        } else {
+            lines.push(String::from("STATUS:NEEDS-ACTION"));
+        }
+        if let Some(priority) = todo.priority {
+            let priority = (priority as u8 - b'A' + 1).min(9);
+            lines.push(format!("PRIORITY:{priority}"));
+        }
+        if !todo.tags.is_empty() {
+            let tags: Vec<String> = todo.tags.iter().map(|tag| escape(tag)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        if let Some(rule) = field(todo, "rrule")
+            .map(str::to_string)
+            .or_else(|| field(todo, "rec").and_then(recurrence_rule))
+        {
+            lines.push(format!("RRULE:{rule}"));
+        }
+        lines.push(String::from("END:VTODO"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// The `uid` field of a todo imported from another calendar, or else `<id>@lazytodo`.
+fn uid(todo: &Todo) -> String {
+    match field(todo, "uid").filter(|uid| !is_lazytodo_uid(uid)) {
+        Some(uid) => uid.to_string(),
+        None => format!("{}@{UID_DOMAIN}", todo.id),
+    }
+}
+
+/// Whether `uid` has the `<id>@lazytodo` form, which only ever names the todo with that id.
+fn is_lazytodo_uid(uid: &str) -> bool {
+    uid.strip_suffix(UID_DOMAIN)
+        .and_then(|id| id.strip_suffix('@'))
+        .is_some_and(|id| id.parse::<usize>().is_ok())
+}
+
+fn field<'a>(todo: &'a Todo, key: &str) -> Option<&'a str> {
+    todo.fields
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+fn set_field(todo: &mut Todo, key: &str, value: Option<String>) {
+    todo.fields.retain(|(k, _)| k != key);
+    if let Some(value) = value {
+        todo.fields.push((key.to_string(), value));
+    }
+}
+
+fn utc(datetime: DateTime<Utc>) -> String {
+    datetime.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// `1w` or `+2m` in todo.txt to `FREQ=WEEKLY;INTERVAL=1` or `FREQ=MONTHLY;INTERVAL=2`.
+fn recurrence_rule(rec: &str) -> Option<String> {
+    let rec = rec.strip_prefix('+').unwrap_or(rec);
+    let (interval, unit) = rec.split_at(rec.len().checked_sub(1)?);
+    let interval: u32 = interval.parse().ok().filter(|i| *i > 0)?;
+    let frequency = match unit {
+        "d" => "DAILY",
+        "w" => "WEEKLY",
+        "m" => "MONTHLY",
+        "y" => "YEARLY",
+        _ => return None,
+    };
+    Some(format!("FREQ={frequency};INTERVAL={interval}"))
+}
+
+/// The todo.txt `rec:` of a rule, `None` for rules with more than a frequency and interval.
+fn recurrence(rule: &str) -> Option<String> {
+    let mut frequency = None;
+    let mut interval = 1;
+    for part in rule.split(';') {
+        match part.split_once('=')? {
+            ("FREQ", "DAILY") => frequency = Some('d'),
+            ("FREQ", "WEEKLY") => frequency = Some('w'),
+            ("FREQ", "MONTHLY") => frequency = Some('m'),
+            ("FREQ", "YEARLY") => frequency = Some('y'),
+            ("INTERVAL", value) => interval = value.parse().ok()?,
+            _ => return None,
+        }
+    }
+    Some(format!("{interval}{}", frequency?))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('n' | 'N') => out.push('\n'),
+                Some(c) => out.push(c),
+                None => out.push('\\'),
+            },
+            (c, false) => out.push(c),
+        }
+    }
+    out
+}
+
+/// Splits a line into lines of at most [`LINE_LENGTH`] bytes, each ending in CRLF.
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LENGTH {
+            out.push_str("\r\n ");
+            // The space starting a continuation line counts towards its length.
+            length = 1;
+        }
+        out.push(c);
+        length += c.len_utf8();
+    }
+    out + "\r\n"
+}
+
+/// Counts of an import.
+#[derive(Default)]
+pub struct Summary {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+/// A property such as `DUE;VALUE=DATE:20250110`, parameters are dropped.
+struct Property {
+    line: usize,
+    name: String,
+    value: String,
+}
+
+/// Adds the `VTODO`s of a calendar, matching existing todos by `UID`.
+///
+/// A `VTODO` whose `UID` is already in the store updates that todo, or is skipped with
+/// `skip_existing`. Nothing is imported when a component cannot be read, the errors give
+/// the line of each problem.
+pub fn import(
+    input: &str,
+    todos: &mut Vec<Todo>,
+    skip_existing: bool,
+) -> Result<Summary, Vec<String>> {
+    let mut components = Vec::new();
+    let mut errors = Vec::new();
+    let mut current: Option<(usize, Vec<Property>)> = None;
+    for property in properties(input) {
+        match (
+            property.name.as_str(),
+            property.value.as_str(),
+            &mut current,
+        ) {
+            ("BEGIN", "VTODO", None) => current = Some((property.line, Vec::new())),
+            ("BEGIN", "VTODO", Some(_)) => {
+                errors.push(format!("line {}: VTODO inside a VTODO", property.line));
+            }
+            ("END", "VTODO", Some(_)) => components.extend(current.take()),
+            (_, _, Some((_, properties))) => properties.push(property),
+            _ => {}
+        }
+    }
+    if let Some((line, _)) = current {
+        errors.push(format!("line {line}: VTODO without END:VTODO"));
+    }
+
+    let mut parsed = Vec::new();
+    for (line, properties) in components {
+        match parse_todo(&properties) {
+            Ok(todo) => parsed.push(todo),
+            Err(err) => errors.push(format!("line {line}: {err}")),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut summary = Summary::default();
+    for (uid, new) in parsed {
+        let existing = todos.iter_mut().find(|t| self::uid(t) == uid);
+        match existing {
+            Some(_) if skip_existing => summary.skipped += 1,
+            Some(todo) => {
+                set_field(todo, "rec", field(&new, "rec").map(str::to_string));
+                set_field(todo, "rrule", field(&new, "rrule").map(str::to_string));
+                todo.text = new.text;
+                todo.done = new.done;
+                todo.completed_at = new.completed_at;
+                todo.due = new.due;
+                todo.priority = new.priority;
+                todo.tags = new.tags;
+                summary.updated += 1;
+            }
+            None => {
+                let mut todo = new;
+                todo.id = todo::next_id(todos);
+                // Keeps the UID of calendars other than lazytodo for the next import. The
+                // UID of a todo from another store is dropped, it would name one of ours.
+                if !is_lazytodo_uid(&uid) {
+                    set_field(&mut todo, "uid", Some(uid));
+                }
+                todos.push(todo);
+                summary.added += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Unfolds the lines of a calendar and splits them into properties.
+fn properties(input: &str) -> Vec<Property> {
+    let mut properties: Vec<Property> = Vec::new();
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ if line.is_empty() => {}
+            _ => lines.push((idx + 1, line.to_string())),
+        }
+    }
+
+    for (line, text) in lines {
+        // A colon in a quoted parameter value does not end the name.
+        let mut quoted = false;
+        let Some(colon) = text.char_indices().find_map(|(idx, c)| {
+            if c == '"' {
+                quoted = !quoted;
+            }
+            (c == ':' && !quoted).then_some(idx)
+        }) else {
+            continue;
+        };
+        let name = text[..colon].split(';').next().unwrap_or_default();
+        properties.push(Property {
+            line,
+            name: name.to_ascii_uppercase(),
+            value: text[colon + 1..].to_string(),
+        });
+    }
+    properties
+}
+
+fn parse_todo(properties: &[Property]) -> Result<(String, Todo), String> {
+    let value = |name: &str| {
+        properties
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.value.as_str())
+    };
+    let uid = value("UID").ok_or("VTODO without UID")?.to_string();
+    let text = unescape(value("SUMMARY").unwrap_or_default());
+    if text.trim().is_empty() {
+        return Err(format!("VTODO {uid} without SUMMARY"));
+    }
+
+    let mut todo = Todo::new(0, text.trim().to_string());
+    if let Some(created) = value("CREATED").or(value("DTSTART")) {
+        todo.created_at = timestamp(created)?;
+    }
+    todo.due = value("DUE")
+        .map(|due| date(due).map(dates::to_timestamp))
+        .transpose()?;
+    todo.done = value("STATUS").is_some_and(|status| status.eq_ignore_ascii_case("COMPLETED"));
+    todo.completed_at = value("COMPLETED").map(timestamp).transpose()?;
+    if todo.completed_at.is_some() {
+        todo.done = true;
+    }
+    todo.priority = match value("PRIORITY").map(|p| p.trim().parse::<u8>()) {
+        // 0 means no priority.
+        Some(Ok(0)) | None => None,
+        Some(Ok(priority @ 1..=9)) => Some((b'A' + priority - 1) as char),
+        Some(_) => return Err(format!("invalid PRIORITY in VTODO {uid}")),
+    };
+    for categories in properties.iter().filter(|p| p.name == "CATEGORIES") {
+        for tag in split_list(&categories.value).map(|t| todo::normalize_tag(&t)) {
+            if !tag.is_empty() && !todo.tags.contains(&tag) {
+                todo.tags.push(tag);
+            }
+        }
+    }
+    if let Some(rule) = value("RRULE") {
+        match recurrence(rule) {
+            Some(rec) => set_field(&mut todo, "rec", Some(rec)),
+            None => set_field(&mut todo, "rrule", Some(rule.to_string())),
+        }
+    }
+    Ok((uid, todo))
+}
+
+/// The comma separated values of a property, commas can be escaped.
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (idx, c) in value.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            ',' if !escaped => {
+                parts.push(&value[start..idx]);
+                start = idx + 1;
+            }
+            _ => escaped = false,
+        }
+    }
+    parts.push(&value[start..]);
+    parts.into_iter().map(unescape)
+}
+
+/// `20250110`, or the day of a date-time.
+fn date(value: &str) -> Result<NaiveDate, String> {
+    let day = value.get(..8).unwrap_or(value);
+    NaiveDate::parse_from_str(day, DATE_FORMAT).map_err(|_| format!("`{value}` is not a date"))
+}
+
+/// `20250110T093000Z` in UTC, or in local time without the `Z`.
+fn timestamp(value: &str) -> Result<i64, String> {
+    let error = || format!("`{value}` is not a date-time");
+    if let Some(utc) = value.strip_suffix('Z') {
+        let datetime = NaiveDateTime::parse_from_str(utc, DATE_TIME_FORMAT).map_err(|_| error())?;
+        return Ok(datetime.and_utc().timestamp_millis());
+    }
+    match NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT) {
+        Ok(datetime) => Local
+            .from_local_datetime(&datetime)
+            .earliest()
+            .map(|datetime| datetime.timestamp_millis())
+            .ok_or_else(error),
+        Err(_) => date(value).map(dates::to_timestamp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap()
+    }
+
+    fn date(date: &str) -> i64 {
+        dates::to_timestamp(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap())
+    }
+
+    fn store() -> Vec<Todo> {
+        let mut deploy = Todo::new(0, String::from("Deploy the api; then, rest"));
+        deploy.created_at = stamp().timestamp_millis();
+        deploy.priority = Some('A');
+        deploy.tags = vec![String::from("ops"), String::from("urgent")];
+        deploy.due = Some(date("2025-01-10"));
+        deploy.fields = vec![(String::from("rec"), String::from("2w"))];
+
+        let mut milk = Todo::new(1, String::from("Buy milk"));
+        milk.created_at = stamp().timestamp_millis();
+        milk.set_done(true);
+        milk.completed_at = Some(stamp().timestamp_millis());
+
+        vec![deploy, milk]
+    }
+
+    #[test]
+    fn export_writes_vtodos() {
+        let exported = export(&store(), stamp());
+        assert_eq!(
+            exported,
+            "BEGIN:VCALENDAR\r\n\
+             VERSION:2.0\r\n\
+             PRODID:-//lazytodo//lazytodo//EN\r\n\
+             BEGIN:VTODO\r\n\
+             UID:0@lazytodo\r\n\
+             DTSTAMP:20250101T120000Z\r\n\
+             CREATED:20250101T120000Z\r\n\
+             SUMMARY:Deploy the api\\; then\\, rest\r\n\
+             DUE;VALUE=DATE:20250110\r\n\
+             STATUS:NEEDS-ACTION\r\n\
+             PRIORITY:1\r\n\
+             CATEGORIES:ops,urgent\r\n\
+             RRULE:FREQ=WEEKLY;INTERVAL=2\r\n\
+             END:VTODO\r\n\
+             BEGIN:VTODO\r\n\
+             UID:1@lazytodo\r\n\
+             DTSTAMP:20250101T120000Z\r\n\
+             CREATED:20250101T120000Z\r\n\
+             SUMMARY:Buy milk\r\n\
+             STATUS:COMPLETED\r\n\
+             COMPLETED:20250101T120000Z\r\n\
+             END:VTODO\r\n\
+             END:VCALENDAR\r\n"
+        );
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let todos = vec![Todo::new(0, "é".repeat(60))];
+        let exported = export(&todos, stamp());
+        assert!(exported.split("\r\n").all(|line| line.len() <= LINE_LENGTH));
+
+        let mut imported = Vec::new();
+        import(&exported, &mut imported, false).unwrap();
+        assert_eq!(imported[0].text, todos[0].text);
+    }
+
+    #[test]
+    fn import_reads_its_export() {
+        let todos = store();
+        let mut imported = Vec::new();
+        let summary = import(&export(&todos, stamp()), &mut imported, false).unwrap();
+        assert_eq!(summary.added, 2);
+
+        for (original, imported) in todos.iter().zip(&imported) {
+            assert_eq!(original.id, imported.id);
+            assert_eq!(original.text, imported.text);
+            assert_eq!(original.done, imported.done);
+            assert_eq!(original.created_at, imported.created_at);
+            assert_eq!(original.completed_at, imported.completed_at);
+            assert_eq!(original.due, imported.due);
+            assert_eq!(original.priority, imported.priority);
+            assert_eq!(original.tags, imported.tags);
+            assert_eq!(original.fields, imported.fields);
+        }
+    }
+
+    #[test]
+    fn import_updates_or_skips_todos_with_the_same_uid() {
+        let mut todos = store();
+        let calendar = "BEGIN:VCALENDAR\n\
+                        BEGIN:VTODO\n\
+                        UID:0@lazytodo\n\
+                        SUMMARY:Deploy the api\n\
+                        STATUS:COMPLETED\n\
+                        END:VTODO\n\
+                        BEGIN:VTODO\n\
+                        UID:abc@example.com\n\
+                        SUMMARY:Call the bank\n\
+                        DUE;TZID=Europe/Rome:20250301T090000\n\
+                        CATEGORIES:Phone\n\
+                        END:VTODO\n\
+                        END:VCALENDAR\n";
+
+        let summary = import(calendar, &mut todos, true).unwrap();
+        assert_eq!((summary.added, summary.updated, summary.skipped), (1, 0, 1));
+        assert!(!todos[0].done);
+        assert_eq!(todos[2].due, Some(date("2025-03-01")));
+        assert_eq!(todos[2].tags, ["phone"]);
+
+        let summary = import(calendar, &mut todos, false).unwrap();
+        assert_eq!((summary.added, summary.updated, summary.skipped), (0, 2, 0));
+        assert!(todos[0].done);
+        assert_eq!(todos[0].text, "Deploy the api");
+        assert_eq!(todos.len(), 3);
+    }
+
+    #[test]
+    fn uids_of_other_stores_are_not_kept() {
+        let mut todos = store();
+        let calendar = "BEGIN:VTODO\nUID:5@lazytodo\nSUMMARY:From another store\nEND:VTODO\n\
+                        BEGIN:VTODO\nUID:7@example.com\nSUMMARY:From a calendar\nEND:VTODO\n";
+        import(calendar, &mut todos, false).unwrap();
+        assert!(todos[2].fields.is_empty());
+        assert_eq!(
+            todos[3].fields,
+            [(String::from("uid"), String::from("7@example.com"))]
+        );
+
+        // Ids 4 and 5 are taken after the import, each todo keeps a UID of its own.
+        todos.push(Todo::new(4, String::from("Water the plants")));
+        todos.push(Todo::new(5, String::from("Renew the domain")));
+        let exported = export(&todos, stamp());
+        let uids: Vec<&str> = exported
+            .lines()
+            .filter_map(|line| line.strip_prefix("UID:"))
+            .collect();
+        assert_eq!(
+            uids,
+            [
+                "0@lazytodo",
+                "1@lazytodo",
+                "2@lazytodo",
+                "7@example.com",
+                "4@lazytodo",
+                "5@lazytodo"
+            ]
+        );
+
+        // Reading it again updates every todo in place.
+        let summary = import(&exported, &mut todos, false).unwrap();
+        assert_eq!((summary.added, summary.updated), (0, 6));
+        assert_eq!(todos[5].text, "Renew the domain");
+    }
+
+    #[test]
+    fn completion_time_is_not_made_up() {
+        let mut todos = store();
+        todos[1].completed_at = None;
+        let exported = export(&todos, stamp());
+        assert!(exported.contains("STATUS:COMPLETED\r\n"));
+        assert!(!exported.contains("COMPLETED:"));
+    }
+
+    #[test]
+    fn import_reports_invalid_components() {
+        let calendar = "BEGIN:VTODO\nSUMMARY:No uid\nEND:VTODO\n\
+                        BEGIN:VTODO\nUID:1\nSUMMARY:x\nDUE:soon\nEND:VTODO\n";
+        let errors = import(calendar, &mut Vec::new(), false).err().unwrap();
+        assert_eq!(
+            errors,
+            ["line 1: VTODO without UID", "line 4: `soon` is not a date"]
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dates,
+    entities::{todo, workspace, Todo, Workspace},
+};
+
+/// Bumped when a field is removed or changes meaning, new fields keep the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The whole store, as written by `lazytodo export --format json`.
+///
+/// The todos are nested in one object, `lazytodo export --format jsonl` writes them in the
+/// lines read back by `lazytodo import --format jsonl`.
+#[derive(Serialize)]
+pub struct Export<'a> {
+    version: u32,
+    workspaces: Vec<WorkspaceRecord<'a>>,
+    todos: Vec<TodoRecord>,
+    archive: Vec<TodoRecord>,
+}
+
+#[derive(Serialize)]
+pub struct WorkspaceRecord<'a> {
+    id: usize,
+    title: &'a str,
+    parent: Option<usize>,
+}
+
+/// A todo in JSON, and one line of a JSON Lines import.
+///
+/// Only `text` is required on import. A todo whose `id` already exists is replaced.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TodoRecord {
+    pub id: Option<usize>,
+    #[serde(default)]
+    pub done: bool,
+    pub text: String,
+    /// Workspace id, a title is accepted too on import.
+    pub workspace: Option<WorkspaceRef>,
+    pub due: Option<NaiveDate>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: Option<DateTime<FixedOffset>>,
+    pub priority: Option<char>,
+    pub completed_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    /// `path:line`
+    pub link: Option<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WorkspaceRef {
+    Id(usize),
+    Title(String),
+}
+
+impl From<&Todo> for TodoRecord {
+    fn from(todo: &Todo) -> Self {
+        Self {
+            id: Some(todo.id),
+            done: todo.done,
+            text: todo.text.clone(),
+            workspace: todo.workspace.map(WorkspaceRef::Id),
+            due: todo.due.and_then(dates::from_timestamp),
+            tags: todo.tags.clone(),
+            created_at: to_datetime(todo.created_at),
+            priority: todo.priority,
+            completed_at: todo.completed_at.and_then(to_datetime),
+            fields: todo.fields.iter().cloned().collect(),
+            link: todo.link.as_ref().map(|link| link.to_string()),
+            notes: todo.notes.clone(),
+        }
+    }
+}
+
+impl TodoRecord {
+    fn into_todo(self, id: usize, workspaces: &[Workspace]) -> Result<Todo, String> {
+        let text = self.text.trim();
+        if text.is_empty() {
+            return Err(String::from("`text` is empty"));
+        }
+
+        let mut todo = Todo::new(id, text.to_string());
+        todo.done = self.done;
+        todo.workspace = match self.workspace {
+            Some(WorkspaceRef::Id(id)) => match workspaces.iter().any(|w| w.id == id) {
+                true => Some(id),
+                false => return Err(format!("no workspace with id {id}")),
+            },
+            Some(WorkspaceRef::Title(title)) => Some(
+                workspace::find(workspaces, &title)
+                    .ok_or_else(|| format!("no workspace named `{title}`"))?
+                    .id,
+            ),
+            None => None,
+        };
+        todo.due = self.due.map(dates::to_timestamp);
+        for tag in self.tags.iter().map(|t| todo::normalize_tag(t)) {
+            if !tag.is_empty() && !todo.tags.contains(&tag) {
+                todo.tags.push(tag);
+            }
+        }
+        if let Some(created_at) = self.created_at {
+            todo.created_at = created_at.timestamp_millis();
+        }
+        todo.priority = match self.priority {
+            Some(priority) if priority.is_ascii_uppercase() => Some(priority),
+            Some(priority) => return Err(format!("priority `{priority}` is not A to Z")),
+            None => None,
+        };
+        todo.completed_at = match (self.done, self.completed_at) {
+            (true, Some(completed_at)) => Some(completed_at.timestamp_millis()),
+            _ => None,
+        };
+        todo.fields = self.fields.into_iter().collect();
+        todo.link = self.link.map(|link| link.parse()).transpose()?;
+        todo.notes = self.notes.trim().to_string();
+        Ok(todo)
+    }
+}
+
+fn to_datetime(millis: i64) -> Option<DateTime<FixedOffset>> {
+    DateTime::from_timestamp_millis(millis).map(|d| d.with_timezone(&Local).fixed_offset())
+}
+
+pub fn export(
+    todos: &[Todo],
+    archive: &[Todo],
+    workspaces: &[Workspace],
+) -> serde_json::Result<String> {
+    let export = Export {
+        version: SCHEMA_VERSION,
+        workspaces: workspaces
+            .iter()
+            .map(|w| WorkspaceRecord {
+                id: w.id,
+                title: &w.title,
+                parent: w.parent,
+            })
+            .collect(),
+        todos: todos.iter().map(TodoRecord::from).collect(),
+        archive: archive.iter().map(TodoRecord::from).collect(),
+    };
+    serde_json::to_string_pretty(&export)
+}
+
+/// One todo per line, archived ones excluded, in the shape read by [`import_lines`].
+pub fn export_lines(todos: &[Todo]) -> serde_json::Result<String> {
+    let mut output = String::new();
+    for todo in todos {
+        output.push_str(&serde_json::to_string(&TodoRecord::from(todo))?);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Adds the todos of a JSON Lines document to `todos`, returning how many were read.
+///
+/// Nothing is changed when a line is invalid, every invalid line is reported instead.
+pub fn import_lines(
+    input: &str,
+    todos: &mut Vec<Todo>,
+    workspaces: &[Workspace],
+) -> Result<usize, Vec<String>> {
+    let mut errors = Vec::new();
+    let mut records = Vec::new();
+    let mut seen: HashMap<usize, usize> = HashMap::new();
+
+    for (idx, line) in input.lines().enumerate() {
+        let number = idx + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<TodoRecord>(line) {
+            Ok(record) => {
+                if let Some(id) = record.id {
+                    if let Some(first) = seen.insert(id, number) {
+                        errors.push((number, format!("id {id} is also used on line {first}")));
+                    }
+                }
+                records.push((number, record));
+            }
+            Err(err) => errors.push((number, err.to_string())),
+        }
+    }
+
+    // Todos without an id are numbered after every id in use, including the imported ones.
+    let mut next_id = seen
+        .keys()
+        .map(|id| id + 1)
+        .max()
+        .unwrap_or(0)
+        .max(todo::next_id(todos));
+    let mut imported = todos.clone();
+    let count = records.len();
+    for (number, record) in records {
+        let id = record.id.unwrap_or_else(|| {
+            next_id += 1;
+            next_id - 1
+        });
+        match record.into_todo(id, workspaces) {
+            Ok(todo) => match imported.iter_mut().find(|t| t.id == id) {
+                Some(existing) => *existing = todo,
+                None => imported.push(todo),
+            },
+            Err(err) => errors.push((number, err)),
+        }
+    }
+
+    if !errors.is_empty() {
+        errors.sort_by_key(|(number, _)| *number);
+        return Err(errors
+            .into_iter()
+            .map(|(number, err)| format!("line {number}: {err}"))
+            .collect());
+    }
+    *todos = imported;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> (Vec<Todo>, Vec<Workspace>) {
+        let workspaces = vec![Workspace::new(0, String::from("infra"))];
+        let mut deploy = Todo::new(0, String::from("Deploy the api"));
+        deploy.workspace = Some(0);
+        deploy.tags = vec![String::from("ops")];
+        deploy.priority = Some('A');
+        deploy.due = Some(dates::to_timestamp(
+            NaiveDate::from_ymd_opt(2025, 1, 10).unwrap(),
+        ));
+        deploy.link = Some("src/main.rs:412".parse().unwrap());
+        deploy.notes = String::from("Ask before the freeze");
+        let mut milk = Todo::new(3, String::from("Buy milk"));
+        milk.set_done(true);
+        (vec![deploy, milk], workspaces)
+    }
+
+    #[test]
+    fn export_lines_are_imported_back() {
+        let (todos, workspaces) = store();
+        let mut imported = Vec::new();
+        let lines = export_lines(&todos).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+        assert_eq!(import_lines(&lines, &mut imported, &workspaces), Ok(2));
+        assert_eq!(imported, todos);
+    }
+
+    #[test]
+    fn import_numbers_todos_after_the_ids_in_use() {
+        let (mut todos, workspaces) = store();
+        let input = "{\"text\": \"Water the plants\", \"workspace\": \"infra\"}\n\
+                     \n\
+                     {\"id\": 7, \"text\": \"Renew the domain\"}\n\
+                     {\"id\": 0, \"text\": \"Deploy the api again\"}\n";
+        assert_eq!(import_lines(input, &mut todos, &workspaces), Ok(3));
+
+        let ids: Vec<_> = todos.iter().map(|t| (t.id, t.text.as_str())).collect();
+        assert_eq!(
+            ids,
+            [
+                (0, "Deploy the api again"),
+                (3, "Buy milk"),
+                (8, "Water the plants"),
+                (7, "Renew the domain")
+            ]
+        );
+        assert_eq!(todos[2].workspace, Some(0));
+    }
+
+    #[test]
+    fn import_reports_every_invalid_line() {
+        let (mut todos, workspaces) = store();
+        let before = todos.clone();
+        let input = "{\"text\": \"Fine\"}\n\
+                     {\"text\": \"Unclosed\"\n\
+                     {\"text\": \"  \"}\n\
+                     {\"text\": \"Elsewhere\", \"workspace\": 4}\n\
+                     {\"text\": \"Nowhere\", \"workspace\": \"home\"}\n\
+                     {\"id\": 5, \"text\": \"First\"}\n\
+                     {\"id\": 5, \"text\": \"Second\", \"priority\": \"a\"}\n\
+                     {\"text\": \"Typo\", \"tag\": [\"ops\"]}\n";
+
+        let errors = import_lines(input, &mut todos, &workspaces).unwrap_err();
+        let lines: Vec<_> = errors
+            .iter()
+            .map(|err| err.split(':').next().unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            ["line 2", "line 3", "line 4", "line 5", "line 7", "line 7", "line 8"]
+        );
+        assert_eq!(errors[1], "line 3: `text` is empty");
+        assert_eq!(errors[2], "line 4: no workspace with id 4");
+        assert_eq!(errors[3], "line 5: no workspace named `home`");
+        assert!(errors.contains(&String::from("line 7: id 5 is also used on line 6")));
+        assert!(errors[6].contains("unknown field `tag`"), "{}", errors[6]);
+        assert_eq!(todos, before);
+    }
+}
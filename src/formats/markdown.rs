@@ -0,0 +1,361 @@
+use chrono::NaiveDate;
+
+use crate::{
+    dates,
+    entities::{todo, workspace, Todo, Workspace},
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A `- [ ]` or `- [x]` line of a Markdown checklist.
+pub struct Item {
+    /// The todo of the line in a file bound to a workspace, from its `<!-- id:4 -->` marker.
+    pub id: Option<usize>,
+    pub done: bool,
+    pub priority: Option<char>,
+    pub text: String,
+    pub tags: Vec<String>,
+    pub due: Option<NaiveDate>,
+}
+
+/// Writes the store as a checklist, with a heading for each workspace.
+pub fn export(todos: &[Todo], workspaces: &[Workspace]) -> String {
+    let mut out = String::from("# Todos\n");
+    let without_workspace: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| {
+            t.workspace
+                .is_none_or(|id| !workspaces.iter().any(|w| w.id == id))
+        })
+        .collect();
+    push_items(&mut out, &without_workspace);
+
+    for row in workspace::all_rows(workspaces) {
+        let workspace = &workspaces[row.index];
+        let level = "#".repeat((row.depth + 2).min(6));
+        out.push_str(&format!("\n{level} {}\n", workspace.title));
+        let items: Vec<&Todo> = todos
+            .iter()
+            .filter(|t| t.workspace == Some(workspace.id))
+            .collect();
+        push_items(&mut out, &items);
+    }
+    out
+}
+
+fn push_items(out: &mut String, todos: &[&Todo]) {
+    if todos.is_empty() {
+        return;
+    }
+    out.push('\n');
+    for todo in todos {
+        out.push_str(&item_line(todo));
+        out.push('\n');
+    }
+}
+
+/// e.g. `- [ ] (A) Deploy the api #ops due:2025-01-10`.
+pub fn item_line(todo: &Todo) -> String {
+    let mark = if todo.done { 'x' } else { ' ' };
+    let mut words = vec![format!("- [{mark}]")];
+    words.extend(todo.priority.map(|priority| format!("({priority})")));
+    words.push(escape_text(&todo.text));
+    words.extend(todo.tags.iter().map(|tag| format!("#{tag}")));
+    words.extend(
+        todo.due
+            .and_then(dates::from_timestamp)
+            .map(|due| format!("due:{}", due.format(DATE_FORMAT))),
+    );
+    words.join(" ")
+}
+
+/// The line of a todo in a file bound to its workspace, ending in its id marker.
+fn marked_line(todo: &Todo) -> String {
+    format!("{} <!-- id:{} -->", item_line(todo), todo.id)
+}
+
+/// Escapes the first and last words of `text` with a backslash when they would be read as
+/// a priority, a tag, a due date or the end of an id marker.
+fn escape_text(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let last = words.len().saturating_sub(1);
+    let mut escaped = Vec::new();
+    for (index, word) in words.into_iter().enumerate() {
+        let special = word.starts_with('\\')
+            || (index == 0 && parse_priority(word).is_some())
+            || (index == last
+                && (parse_tag(word).is_some()
+                    || parse_due(word).is_some()
+                    || word.ends_with("-->")));
+        match special {
+            true => escaped.push(format!("\\{word}")),
+            false => escaped.push(word.to_string()),
+        }
+    }
+    escaped.join(" ")
+}
+
+fn unescape(word: &str) -> &str {
+    word.strip_prefix('\\').unwrap_or(word)
+}
+
+fn parse_priority(word: &str) -> Option<char> {
+    match word.as_bytes() {
+        [b'(', priority, b')'] if priority.is_ascii_uppercase() => Some(*priority as char),
+        _ => None,
+    }
+}
+
+fn parse_tag(word: &str) -> Option<&str> {
+    word.strip_prefix('#').filter(|tag| !tag.is_empty())
+}
+
+fn parse_due(word: &str) -> Option<NaiveDate> {
+    let due = word.strip_prefix("due:")?;
+    NaiveDate::parse_from_str(due, DATE_FORMAT).ok()
+}
+
+/// Splits the `<!-- id:4 -->` marker off the end of a line.
+fn split_id(line: &str) -> (&str, Option<usize>) {
+    let marker = line.rsplit_once("<!--").and_then(|(rest, marker)| {
+        let id = marker.trim_end().strip_suffix("-->")?.trim();
+        Some((rest, id.strip_prefix("id:")?.parse().ok()?))
+    });
+    match marker {
+        Some((rest, id)) => (rest, Some(id)),
+        None => (line, None),
+    }
+}
+
+fn parse_item(line: &str) -> Option<Item> {
+    let rest = line
+        .trim_start()
+        .strip_prefix("- [")
+        .or_else(|| line.trim_start().strip_prefix("* ["))?;
+    let (done, rest) = match rest.get(..2)? {
+        " ]" => (false, &rest[2..]),
+        "x]" | "X]" => (true, &rest[2..]),
+        _ => return None,
+    };
+    let (rest, id) = split_id(rest);
+
+    let mut words: Vec<&str> = rest.split_whitespace().collect();
+    let priority = words.first().and_then(|word| parse_priority(word));
+    if priority.is_some() {
+        words.remove(0);
+    }
+
+    // Tags and the due date are only recognised after the text.
+    let mut tags = Vec::new();
+    let mut due = None;
+    while let Some(word) = words.last() {
+        if let Some(tag) = parse_tag(word) {
+            tags.insert(0, todo::normalize_tag(tag));
+        } else if let Some(date) = parse_due(word) {
+            due = Some(date);
+        } else {
+            break;
+        }
+        words.pop();
+    }
+
+    // Only the first and last words are escaped, see `escape_text`.
+    if let Some(first) = words.first_mut() {
+        *first = unescape(first);
+    }
+    if let [_, .., last] = words.as_mut_slice() {
+        *last = unescape(last);
+    }
+
+    Some(Item {
+        id,
+        done,
+        priority,
+        text: words.join(" "),
+        tags,
+        due,
+    })
+}
+
+/// Every checklist item of a Markdown document, in order.
+pub fn parse_items(input: &str) -> Vec<Item> {
+    input
+        .lines()
+        .filter_map(parse_item)
+        .filter(|item| !item.text.is_empty())
+        .collect()
+}
+
+/// Writes `todos` into the checklist of `document`, keeping every other line.
+///
+/// Each item is rewritten in place with the id marker of its todo, found by the marker or
+/// else by the text. Items whose todo is gone are dropped, unmarked items without a todo
+/// are kept for the next merge and todos without an item are added at the end.
+pub fn replace_items(document: &str, todos: &[&Todo]) -> String {
+    let items: Vec<Option<Item>> = document.lines().map(parse_item).collect();
+    // Todos are only found by their text when no item carries their marker.
+    let marked: Vec<usize> = items.iter().flatten().filter_map(|item| item.id).collect();
+    let mut written: Vec<usize> = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+
+    for (line, item) in document.lines().zip(items) {
+        let Some(item) = item else {
+            lines.push(line.to_string());
+            continue;
+        };
+        let found = match item.id.filter(|id| !written.contains(id)) {
+            Some(id) => match todos.iter().find(|t| t.id == id) {
+                Some(todo) => Some(todo),
+                // Deleted or moved to another workspace.
+                None => continue,
+            },
+            None => todos.iter().find(|t| {
+                !written.contains(&t.id) && !marked.contains(&t.id) && t.text == item.text
+            }),
+        };
+        match found {
+            Some(todo) => {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                lines.push(format!("{indent}{}", marked_line(todo)));
+                written.push(todo.id);
+            }
+            None => lines.push(line.to_string()),
+        }
+    }
+
+    let added: Vec<String> = todos
+        .iter()
+        .filter(|t| !written.contains(&t.id))
+        .map(|todo| marked_line(todo))
+        .collect();
+    if !added.is_empty() {
+        if lines
+            .last()
+            .is_some_and(|line| !line.trim().is_empty() && parse_item(line).is_none())
+        {
+            lines.push(String::new());
+        }
+        lines.extend(added);
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: usize, text: &str) -> Todo {
+        let mut todo = Todo::new(id, text.to_string());
+        todo.workspace = Some(0);
+        todo
+    }
+
+    #[test]
+    fn parses_items() {
+        let items = parse_items(
+            "# Todos\n\
+             - [ ] (A) Deploy the api #ops due:2025-01-10 <!-- id:4 -->\n\
+             \x20 * [X] Buy milk\n\
+             - [ ] Fix #123 in the parser #Bugs\n\
+             - [ ]\n\
+             - [?] Not an item\n",
+        );
+        let parsed: Vec<_> = items
+            .iter()
+            .map(|i| (i.id, i.done, i.priority, i.text.as_str(), i.tags.clone()))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                (
+                    Some(4),
+                    false,
+                    Some('A'),
+                    "Deploy the api",
+                    vec![String::from("ops")]
+                ),
+                (None, true, None, "Buy milk", vec![]),
+                (
+                    None,
+                    false,
+                    None,
+                    "Fix #123 in the parser",
+                    vec![String::from("bugs")]
+                ),
+            ]
+        );
+        assert_eq!(items[0].due, NaiveDate::from_ymd_opt(2025, 1, 10));
+    }
+
+    #[test]
+    fn text_that_looks_like_tokens_is_escaped() {
+        for text in [
+            "Close #123",
+            "(B) is not a priority",
+            "Move the due:2025-01-10 deadline",
+            "Ends in due:2025-01-10",
+            "\\o/ and \\",
+            "Comment out <!-- id:3 -->",
+            "#",
+        ] {
+            let mut todo = todo(3, text);
+            todo.tags = vec![String::from("ops")];
+            let items = parse_items(&marked_line(&todo));
+            assert_eq!(items[0].text, text);
+            assert_eq!(items[0].tags, todo.tags);
+            assert_eq!(items[0].id, Some(3));
+        }
+        assert_eq!(item_line(&todo(0, "Close #123")), "- [ ] Close \\#123");
+    }
+
+    #[test]
+    fn replace_items_rewrites_items_in_place() {
+        let document = "# Plan\n\
+                        \n\
+                        ## Now\n\
+                        - [ ] Write it <!-- id:0 -->\n\
+                        - [ ] Drop it <!-- id:1 -->\n\
+                        \n\
+                        ## Later\n\
+                        - [ ] Ship it <!-- id:2 -->\n\
+                        - [ ] Added in the file\n\
+                        \x20 - [ ] Nested <!-- id:3 -->\n\
+                        \n\
+                        Notes stay here.\n";
+        let mut write = todo(0, "Write it");
+        write.done = true;
+        let ship = todo(2, "Ship it faster");
+        let nested = todo(3, "Nested");
+        let new = todo(5, "New in the store");
+
+        assert_eq!(
+            replace_items(document, &[&write, &ship, &new, &nested]),
+            "# Plan\n\
+             \n\
+             ## Now\n\
+             - [x] Write it <!-- id:0 -->\n\
+             \n\
+             ## Later\n\
+             - [ ] Ship it faster <!-- id:2 -->\n\
+             - [ ] Added in the file\n\
+             \x20 - [ ] Nested <!-- id:3 -->\n\
+             \n\
+             Notes stay here.\n\
+             \n\
+             - [ ] New in the store <!-- id:5 -->\n"
+        );
+    }
+
+    #[test]
+    fn replace_items_marks_items_found_by_text() {
+        let milk = todo(7, "Buy milk");
+        assert_eq!(
+            replace_items("# Home\n- [ ] Buy milk\n", &[&milk]),
+            "# Home\n- [ ] Buy milk <!-- id:7 -->\n"
+        );
+        assert_eq!(
+            replace_items("# Home\n", &[&milk]),
+            "# Home\n\n- [ ] Buy milk <!-- id:7 -->\n"
+        );
+    }
+}
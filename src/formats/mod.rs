@@ -0,0 +1,7 @@
+pub mod csv;
+pub mod ics;
+pub mod json;
+pub mod markdown;
+pub mod org;
+pub mod taskwarrior;
+pub mod todotxt;
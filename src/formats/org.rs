@@ -0,0 +1,534 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+use crate::{
+    dates,
+    entities::{todo, workspace, Todo, Workspace},
+};
+
+/// Property holding the todo id, `ID` is left to org-id.
+const ID_PROPERTY: &str = "LAZYTODO_ID";
+const CREATED_PROPERTY: &str = "CREATED_AT";
+const LINK_PROPERTY: &str = "LINK";
+/// The todo.txt field used for the scheduled date, as `t:` in todo.txt tools.
+const SCHEDULED_FIELD: &str = "t";
+/// Milliseconds in a minute, the precision of the `CREATED_AT` property.
+const MINUTE: i64 = 60_000;
+
+/// Writes the store as an Org document, see <https://orgmode.org/manual/Headlines.html>.
+///
+/// Workspaces become headlines, nested as in the tree, and todos `TODO` or `DONE`
+/// headlines one level below their workspace. The id, creation date, link and fields of a
+/// todo are kept in its property drawer and its notes in the body.
+pub fn export(todos: &[Todo], workspaces: &[Workspace]) -> String {
+    let mut out = String::new();
+    for todo in todos.iter().filter(|t| {
+        t.workspace
+            .is_none_or(|id| !workspaces.iter().any(|w| w.id == id))
+    }) {
+        push_todo(&mut out, todo, 1);
+    }
+
+    for row in workspace::all_rows(workspaces) {
+        let workspace = &workspaces[row.index];
+        out.push_str(&format!(
+            "{} {}\n",
+            "*".repeat(row.depth + 1),
+            workspace.title
+        ));
+        for todo in todos.iter().filter(|t| t.workspace == Some(workspace.id)) {
+            push_todo(&mut out, todo, row.depth + 2);
+        }
+    }
+    out
+}
+
+fn push_todo(out: &mut String, todo: &Todo, level: usize) {
+    let keyword = if todo.done { "DONE" } else { "TODO" };
+    let mut headline = format!("{} {keyword} ", "*".repeat(level));
+    if let Some(priority) = todo.priority {
+        headline.push_str(&format!("[#{priority}] "));
+    }
+    headline.push_str(&todo.text);
+    if !todo.tags.is_empty() {
+        headline.push_str(&format!(" :{}:", todo.tags.join(":")));
+    }
+    out.push_str(&headline);
+    out.push('\n');
+
+    let indent = " ".repeat(level + 1);
+    let mut planning = Vec::new();
+    if let Some(due) = todo.due.and_then(dates::from_timestamp) {
+        planning.push(format!("DEADLINE: <{}>", due.format("%Y-%m-%d %a")));
+    }
+    if let Some(scheduled) = field(todo, SCHEDULED_FIELD).and_then(parse_date) {
+        planning.push(format!("SCHEDULED: <{}>", scheduled.format("%Y-%m-%d %a")));
+    }
+    if let Some(closed) = todo.completed_at.filter(|_| todo.done).and_then(timestamp) {
+        planning.push(format!("CLOSED: [{closed}]"));
+    }
+    if !planning.is_empty() {
+        out.push_str(&format!("{indent}{}\n", planning.join(" ")));
+    }
+
+    let mut properties = vec![(ID_PROPERTY.to_string(), todo.id.to_string())];
+    properties.extend(timestamp(todo.created_at).map(|d| (CREATED_PROPERTY.to_string(), d)));
+    properties.extend(
+        todo.link
+            .as_ref()
+            .map(|link| (LINK_PROPERTY.to_string(), link.to_string())),
+    );
+    properties.extend(
+        todo.fields
+            .iter()
+            .filter(|(key, _)| key != SCHEDULED_FIELD)
+            .cloned(),
+    );
+    out.push_str(&format!("{indent}:PROPERTIES:\n"));
+    for (key, value) in properties {
+        out.push_str(&format!("{indent}:{key}: {value}\n"));
+    }
+    out.push_str(&format!("{indent}:END:\n"));
+
+    for line in todo.notes.lines() {
+        out.push_str(&format!("{indent}{line}\n"));
+    }
+}
+
+fn field<'a>(todo: &'a Todo, key: &str) -> Option<&'a str> {
+    todo.fields
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+/// An inactive timestamp such as `2024-12-14 Sat 10:30`, without the brackets.
+fn timestamp(millis: i64) -> Option<String> {
+    DateTime::from_timestamp_millis(millis).map(|d| {
+        d.with_timezone(&Local)
+            .format("%Y-%m-%d %a %H:%M")
+            .to_string()
+    })
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// `<2025-01-10 Fri>` or `[2024-12-14 Sat 10:30]`, as a local date and time.
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    let inner = value
+        .trim()
+        .strip_prefix(['<', '['])?
+        .strip_suffix(['>', ']'])?;
+    let mut words = inner.split_whitespace();
+    let date = parse_date(words.next()?)?;
+    // The weekday is optional and the time follows it.
+    let time = words
+        .find_map(|word| NaiveTime::parse_from_str(word, "%H:%M").ok())
+        .unwrap_or_default();
+    Some(date.and_time(time))
+}
+
+fn local_millis(datetime: NaiveDateTime) -> Option<i64> {
+    Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        .map(|d| d.timestamp_millis())
+}
+
+/// A headline split into its parts.
+struct Headline<'a> {
+    level: usize,
+    keyword: Option<&'a str>,
+    priority: Option<char>,
+    title: &'a str,
+    tags: Vec<&'a str>,
+}
+
+fn parse_headline(line: &str) -> Option<Headline<'_>> {
+    let level = line.chars().take_while(|c| *c == '*').count();
+    let rest = line[level..].strip_prefix(' ')?.trim();
+    if level == 0 {
+        return None;
+    }
+
+    let (keyword, rest) = match rest.split_once(' ').unwrap_or((rest, "")) {
+        (keyword @ ("TODO" | "DONE" | "NEXT" | "WAITING" | "CANCELLED"), rest) => {
+            (Some(keyword), rest.trim_start())
+        }
+        _ => (None, rest),
+    };
+    let (priority, rest) = match rest.as_bytes() {
+        [b'[', b'#', priority, b']', ..] if priority.is_ascii_uppercase() => {
+            (Some(*priority as char), rest[4..].trim_start())
+        }
+        _ => (None, rest),
+    };
+    // Tags are the last word when it looks like `:a:b:`.
+    let (title, tags) = match rest.rsplit_once(' ') {
+        Some((title, tags)) if tags.len() > 1 && tags.starts_with(':') && tags.ends_with(':') => {
+            let tags = tags.split(':').filter(|t| !t.is_empty()).collect();
+            (title.trim_end(), tags)
+        }
+        _ => (rest, Vec::new()),
+    };
+    Some(Headline {
+        level,
+        keyword,
+        priority,
+        title,
+        tags,
+    })
+}
+
+/// Adds the todos of an Org document, returning how many were read.
+///
+/// Headlines without a todo keyword are workspaces, matched by title under the same
+/// parent and created when missing. A todo with the id and creation time of an existing
+/// todo replaces it, other todos get a new id: an id alone may come from another store.
+/// Nothing is imported when a line cannot be read.
+pub fn import(
+    input: &str,
+    todos: &mut Vec<Todo>,
+    workspaces: &mut Vec<Workspace>,
+) -> Result<usize, Vec<String>> {
+    let mut new_workspaces = workspaces.clone();
+    // Todos with the id and creation time read from their property drawer.
+    let mut parsed: Vec<(Option<usize>, Option<i64>, Todo)> = Vec::new();
+    let mut errors = Vec::new();
+    // Workspace headlines enclosing the current line, with their level.
+    let mut path: Vec<(usize, usize)> = Vec::new();
+    // Whether the lines belong to the last todo, rather than to a workspace headline.
+    let mut in_todo = false;
+    // Whether the lines after the current todo headline are still its planning line and
+    // property drawer.
+    let mut in_header = false;
+    let mut in_drawer = false;
+    // Level of the current todo headline, its body is indented one space deeper.
+    let mut level = 0;
+
+    for (idx, line) in input.lines().enumerate() {
+        let error = |message: String| format!("line {}: {message}", idx + 1);
+        if let Some(headline) = parse_headline(line) {
+            in_header = false;
+            in_drawer = false;
+            path.retain(|(level, _)| *level < headline.level);
+            in_todo = headline.keyword.is_some();
+            let Some(keyword) = headline.keyword else {
+                let parent = path.last().map(|(_, id)| *id);
+                let (id, _) =
+                    workspace::find_or_add_child(&mut new_workspaces, parent, headline.title);
+                path.push((headline.level, id));
+                continue;
+            };
+            if headline.title.is_empty() {
+                errors.push(error(String::from("headline without a title")));
+                continue;
+            }
+
+            let mut todo = Todo::new(0, headline.title.to_string());
+            todo.done = matches!(keyword, "DONE" | "CANCELLED");
+            todo.priority = headline.priority;
+            todo.workspace = path.last().map(|(_, id)| *id);
+            for tag in headline.tags.into_iter().map(todo::normalize_tag) {
+                if !todo.tags.contains(&tag) {
+                    todo.tags.push(tag);
+                }
+            }
+            parsed.push((None, None, todo));
+            level = headline.level;
+            in_header = true;
+            continue;
+        }
+
+        let Some((id, created, todo)) = parsed.last_mut().filter(|_| in_todo) else {
+            continue;
+        };
+        let trimmed = line.trim();
+        if in_drawer {
+            if trimmed.eq_ignore_ascii_case(":END:") {
+                in_drawer = false;
+                continue;
+            }
+            let Some((key, value)) = trimmed
+                .strip_prefix(':')
+                .and_then(|rest| rest.split_once(':'))
+                .map(|(key, value)| (key, value.trim()))
+            else {
+                errors.push(error(format!("`{trimmed}` is not a property")));
+                continue;
+            };
+            match key.to_ascii_uppercase().as_str() {
+                ID_PROPERTY => match value.parse() {
+                    Ok(value) => *id = Some(value),
+                    Err(_) => errors.push(error(format!("`{value}` is not an id"))),
+                },
+                CREATED_PROPERTY => match parse_timestamp(&format!("[{value}]")) {
+                    Some(datetime) => {
+                        todo.created_at = local_millis(datetime).unwrap_or_default();
+                        *created = Some(todo.created_at);
+                    }
+                    None => errors.push(error(format!("`{value}` is not a date"))),
+                },
+                LINK_PROPERTY => match value.parse() {
+                    Ok(link) => todo.link = Some(link),
+                    Err(err) => errors.push(error(err)),
+                },
+                _ => todo.fields.push((key.to_string(), value.to_string())),
+            }
+            continue;
+        }
+        if in_header && trimmed.eq_ignore_ascii_case(":PROPERTIES:") {
+            in_drawer = true;
+            continue;
+        }
+        if in_header && is_planning(trimmed) {
+            if let Err(err) = read_planning(trimmed, todo) {
+                errors.push(error(err));
+            }
+            continue;
+        }
+        in_header = false;
+        if !todo.notes.is_empty() || !trimmed.is_empty() {
+            todo.notes.push_str(unindent(line.trim_end(), level));
+            todo.notes.push('\n');
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    *workspaces = new_workspaces;
+    let count = parsed.len();
+    for (id, created, mut todo) in parsed {
+        todo.notes = todo.notes.trim_end().to_string();
+        // The property only has minutes, the todo keeps its exact creation time.
+        let existing = id.zip(created).and_then(|(id, created)| {
+            todos.iter_mut().find(|t| {
+                t.id == id && t.created_at.div_euclid(MINUTE) == created.div_euclid(MINUTE)
+            })
+        });
+        match existing {
+            Some(existing) => {
+                todo.id = existing.id;
+                todo.created_at = existing.created_at;
+                *existing = todo;
+            }
+            None => {
+                todo.id = todo::next_id(todos);
+                todos.push(todo);
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// `line` without the indentation of the body of a headline at `level`, deeper
+/// indentation is part of the notes.
+fn unindent(line: &str, level: usize) -> &str {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    &line[indent.min(level + 1)..]
+}
+
+fn is_planning(line: &str) -> bool {
+    ["DEADLINE:", "SCHEDULED:", "CLOSED:"]
+        .iter()
+        .any(|keyword| line.starts_with(keyword))
+}
+
+/// Reads a line such as `DEADLINE: <2025-01-10 Fri> CLOSED: [2025-01-09 Thu 18:00]`.
+fn read_planning(line: &str, todo: &mut Todo) -> Result<(), String> {
+    let mut rest = line;
+    while let Some((keyword, after)) = rest.split_once(':') {
+        let after = after.trim_start();
+        let end = after
+            .find(['>', ']'])
+            .ok_or_else(|| format!("unterminated timestamp after {keyword}"))?;
+        let value = &after[..=end];
+        let datetime =
+            parse_timestamp(value).ok_or_else(|| format!("`{value}` is not a timestamp"))?;
+        match keyword.trim() {
+            "DEADLINE" => todo.due = Some(dates::to_timestamp(datetime.date())),
+            "SCHEDULED" => {
+                let date = datetime.date().format("%Y-%m-%d").to_string();
+                todo.fields.retain(|(key, _)| key != SCHEDULED_FIELD);
+                todo.fields.push((SCHEDULED_FIELD.to_string(), date));
+            }
+            "CLOSED" => todo.completed_at = local_millis(datetime),
+            keyword => return Err(format!("unknown planning keyword {keyword}")),
+        }
+        rest = &after[end + 1..];
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::todo::Link;
+
+    fn datetime(value: &str) -> i64 {
+        let datetime = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap();
+        local_millis(datetime).unwrap()
+    }
+
+    fn store() -> (Vec<Todo>, Vec<Workspace>) {
+        let mut workspaces = vec![
+            Workspace::new(0, String::from("Infra")),
+            Workspace::new(1, String::from("Staging")),
+        ];
+        workspaces[1].parent = Some(0);
+
+        let mut deploy = Todo::new(3, String::from("Deploy the api"));
+        deploy.created_at = datetime("2024-12-14 10:30");
+        deploy.priority = Some('A');
+        deploy.workspace = Some(1);
+        deploy.tags = vec![String::from("ops"), String::from("urgent")];
+        deploy.due = Some(dates::to_timestamp(parse_date("2025-01-10").unwrap()));
+        deploy.fields = vec![
+            (String::from("rec"), String::from("1w")),
+            (String::from("t"), String::from("2025-01-05")),
+        ];
+        deploy.link = Some(Link {
+            path: String::from("src/main.rs"),
+            line: Some(412),
+        });
+        deploy.notes = String::from("Check the logs first\n  - api\n  - worker\nthen restart");
+
+        let mut milk = Todo::new(5, String::from("Buy milk"));
+        milk.created_at = datetime("2024-12-14 09:00");
+        milk.done = true;
+        milk.completed_at = Some(datetime("2024-12-20 18:00"));
+
+        (vec![milk, deploy], workspaces)
+    }
+
+    #[test]
+    fn export_writes_headlines() {
+        let (todos, workspaces) = store();
+        assert_eq!(
+            export(&todos, &workspaces),
+            "* DONE Buy milk\n  \
+             CLOSED: [2024-12-20 Fri 18:00]\n  \
+             :PROPERTIES:\n  \
+             :LAZYTODO_ID: 5\n  \
+             :CREATED_AT: 2024-12-14 Sat 09:00\n  \
+             :END:\n\
+             * Infra\n\
+             ** Staging\n\
+             *** TODO [#A] Deploy the api :ops:urgent:\n    \
+             DEADLINE: <2025-01-10 Fri> SCHEDULED: <2025-01-05 Sun>\n    \
+             :PROPERTIES:\n    \
+             :LAZYTODO_ID: 3\n    \
+             :CREATED_AT: 2024-12-14 Sat 10:30\n    \
+             :LINK: src/main.rs:412\n    \
+             :rec: 1w\n    \
+             :END:\n    \
+             Check the logs first\n      \
+             - api\n      \
+             - worker\n    \
+             then restart\n"
+        );
+    }
+
+    #[test]
+    fn export_then_import_preserves_the_store() {
+        let (todos, workspaces) = store();
+        let exported = export(&todos, &workspaces);
+
+        let mut imported = Vec::new();
+        let mut imported_workspaces = Vec::new();
+        let count = import(&exported, &mut imported, &mut imported_workspaces).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(imported_workspaces.len(), 2);
+        assert_eq!(
+            imported_workspaces[1].parent,
+            Some(imported_workspaces[0].id)
+        );
+        assert_eq!(export(&imported, &imported_workspaces), {
+            // Ids are only kept when they replace an existing todo.
+            exported
+                .replace("LAZYTODO_ID: 5", "LAZYTODO_ID: 0")
+                .replace("LAZYTODO_ID: 3", "LAZYTODO_ID: 1")
+        });
+
+        // Importing into the original store replaces the todos with the same ids.
+        let (mut original, mut original_workspaces) = store();
+        import(&exported, &mut original, &mut original_workspaces).unwrap();
+        assert_eq!(original.len(), 2);
+        assert_eq!(original_workspaces.len(), 2);
+        assert_eq!(export(&original, &original_workspaces), exported);
+    }
+
+    #[test]
+    fn import_keeps_todos_of_another_store() {
+        let input = "* TODO Water the plants\n  \
+                     :PROPERTIES:\n  \
+                     :LAZYTODO_ID: 5\n  \
+                     :CREATED_AT: 2025-02-01 Sat 08:15\n  \
+                     :END:\n\
+                     * TODO Buy milk\n  \
+                     :PROPERTIES:\n  \
+                     :LAZYTODO_ID: 3\n  \
+                     :END:\n";
+        let (mut todos, mut workspaces) = store();
+        assert_eq!(import(input, &mut todos, &mut workspaces), Ok(2));
+
+        let texts: Vec<&str> = todos.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            ["Buy milk", "Deploy the api", "Water the plants", "Buy milk"]
+        );
+        assert_eq!(todos[2].id, 6);
+        assert_eq!(todos[2].created_at, datetime("2025-02-01 08:15"));
+        assert_eq!(todos[3].id, 7);
+    }
+
+    #[test]
+    fn import_reads_hand_written_files() {
+        let input = "#+TITLE: Agenda\n\
+                     * Home\n\
+                     Some notes about the house.\n\
+                     ** TODO Fix the fence :outdoor:\n   \
+                     SCHEDULED: <2025-03-01 Sat>\n   \
+                     Needs:\n     \
+                     - nails\n\
+                     ** NEXT [#B] Paint the door\n\
+                     *** DONE Buy paint\n\
+                     * Work\n\
+                     ** CANCELLED Old meeting\n";
+        let mut todos = Vec::new();
+        let mut workspaces = Vec::new();
+        assert_eq!(import(input, &mut todos, &mut workspaces), Ok(4));
+
+        let titles: Vec<&str> = workspaces.iter().map(|w| w.title.as_str()).collect();
+        assert_eq!(titles, ["Home", "Work"]);
+        assert_eq!(todos[0].tags, ["outdoor"]);
+        assert_eq!(todos[0].notes, "Needs:\n  - nails");
+        assert_eq!(
+            todos[0].fields,
+            [(String::from("t"), String::from("2025-03-01"))]
+        );
+        assert_eq!(todos[1].priority, Some('B'));
+        assert!(!todos[1].done);
+        assert!(todos[2].done);
+        assert_eq!(todos[2].workspace, Some(0));
+        assert_eq!(todos[3].workspace, Some(1));
+        assert!(todos[3].done);
+    }
+
+    #[test]
+    fn import_reports_invalid_lines() {
+        let input = "* TODO Broken\n  DEADLINE: <soon>\n  :PROPERTIES:\n  oops\n  :END:\n";
+        let mut workspaces = Vec::new();
+        let errors = import(input, &mut Vec::new(), &mut workspaces).unwrap_err();
+        assert_eq!(
+            errors,
+            [
+                "line 2: `<soon>` is not a timestamp",
+                "line 4: `oops` is not a property"
+            ]
+        );
+        assert!(workspaces.is_empty());
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+use crate::{
+    dates,
+    entities::{todo, workspace, Todo, Workspace},
+};
+
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// A task of `task export`, the attributes lazytodo has no use for are ignored.
+#[derive(Deserialize)]
+struct Task {
+    uuid: Option<String>,
+    description: String,
+    status: String,
+    entry: Option<String>,
+    end: Option<String>,
+    due: Option<String>,
+    project: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    priority: Option<String>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    depends: Option<Depends>,
+}
+
+#[derive(Deserialize)]
+struct Annotation {
+    entry: Option<String>,
+    description: String,
+}
+
+/// A list of uuids, or a comma separated string before Taskwarrior 2.6.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Depends {
+    List(Vec<String>),
+    Text(String),
+}
+
+/// What an import created, and the tasks it left out.
+#[derive(Default)]
+pub struct Report {
+    /// Titles of the new workspaces, with their parents, e.g. `Home/Garden`.
+    pub workspaces: Vec<String>,
+    /// Ids of the new todos.
+    pub todos: Vec<usize>,
+    pub deleted: usize,
+    /// Recurring tasks are templates, Taskwarrior exports their instances separately.
+    pub recurring: usize,
+    /// Tasks whose uuid an earlier import already added.
+    pub existing: usize,
+}
+
+/// Adds the tasks of `task export`, a JSON array or one task per line.
+///
+/// A project such as `Home.Garden` becomes the workspace `Garden` inside `Home`, created
+/// when missing. Priorities `H`, `M` and `L` become `A`, `B` and `C`, annotations become
+/// the notes and the uuid and dependencies are kept in the `uuid` and `depends` fields.
+pub fn import(
+    input: &str,
+    todos: &mut Vec<Todo>,
+    workspaces: &mut Vec<Workspace>,
+) -> Result<Report, String> {
+    let tasks: Vec<Task> = match input.trim_start().starts_with('[') {
+        true => serde_json::from_str(input).map_err(|err| err.to_string())?,
+        false => input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                serde_json::from_str(line).map_err(|err| format!("line {}: {err}", idx + 1))
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    let mut report = Report::default();
+    // Uuids of the todos in the store and of the new ones, to resolve dependencies.
+    let mut ids: HashMap<String, usize> = todos
+        .iter()
+        .filter_map(|t| Some((field(t, "uuid")?.to_string(), t.id)))
+        .collect();
+    let mut imported = Vec::new();
+
+    for task in tasks {
+        match task.status.as_str() {
+            "deleted" => {
+                report.deleted += 1;
+                continue;
+            }
+            "recurring" => {
+                report.recurring += 1;
+                continue;
+            }
+            _ => {}
+        }
+        if task
+            .uuid
+            .as_ref()
+            .is_some_and(|uuid| ids.contains_key(uuid))
+        {
+            report.existing += 1;
+            continue;
+        }
+
+        let text = task.description.trim();
+        if text.is_empty() {
+            return Err(format!(
+                "task {} has no description",
+                task.uuid.as_deref().unwrap_or_default()
+            ));
+        }
+        let mut todo = Todo::new(todo::next_id(todos), text.to_string());
+        if let Some(entry) = &task.entry {
+            todo.created_at = timestamp(entry)?;
+        }
+        if task.status == "completed" {
+            todo.done = true;
+            todo.completed_at = Some(match &task.end {
+                Some(end) => timestamp(end)?,
+                None => todo.created_at,
+            });
+        }
+        todo.due = task
+            .due
+            .as_deref()
+            .map(|due| timestamp(due).and_then(day))
+            .transpose()?;
+        todo.priority = match task.priority.as_deref() {
+            Some("H") => Some('A'),
+            Some("M") => Some('B'),
+            Some("L") => Some('C'),
+            _ => None,
+        };
+        for tag in task.tags.iter().map(|tag| todo::normalize_tag(tag)) {
+            if !tag.is_empty() && !todo.tags.contains(&tag) {
+                todo.tags.push(tag);
+            }
+        }
+        todo.notes = task
+            .annotations
+            .iter()
+            .map(|annotation| {
+                let date = annotation.entry.as_deref().map(timestamp).transpose()?;
+                Ok(match date.and_then(dates::from_timestamp) {
+                    Some(date) => format!("{} {}", date.format("%Y-%m-%d"), annotation.description),
+                    None => annotation.description.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?
+            .join("\n");
+        if let Some(project) = &task.project {
+            todo.workspace = find_or_add_project(workspaces, project, &mut report);
+        }
+        if let Some(uuid) = &task.uuid {
+            ids.insert(uuid.clone(), todo.id);
+            todo.fields.push((String::from("uuid"), uuid.clone()));
+        }
+
+        report.todos.push(todo.id);
+        imported.push((todo.id, task.depends));
+        todos.push(todo);
+    }
+
+    // Dependencies can point to tasks later in the export.
+    for (id, depends) in imported {
+        let uuids = match depends {
+            Some(Depends::List(uuids)) => uuids,
+            Some(Depends::Text(uuids)) => uuids.split(',').map(str::to_string).collect(),
+            None => continue,
+        };
+        let depends: Vec<String> = uuids
+            .iter()
+            .filter_map(|uuid| ids.get(uuid.trim()))
+            .map(|id| id.to_string())
+            .collect();
+        if let (false, Some(todo)) = (depends.is_empty(), todos.iter_mut().find(|t| t.id == id)) {
+            todo.fields
+                .push((String::from("depends"), depends.join(",")));
+        }
+    }
+    Ok(report)
+}
+
+fn field<'a>(todo: &'a Todo, key: &str) -> Option<&'a str> {
+    todo.fields
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+/// The workspace of a project such as `Home.Garden`, creating the missing levels.
+fn find_or_add_project(
+    workspaces: &mut Vec<Workspace>,
+    project: &str,
+    report: &mut Report,
+) -> Option<usize> {
+    let mut parent = None;
+    let mut path = Vec::new();
+    for title in project.split('.').map(str::trim).filter(|t| !t.is_empty()) {
+        path.push(title);
+        let (id, added) = workspace::find_or_add_child(workspaces, parent, title);
+        if added {
+            report.workspaces.push(path.join("/"));
+        }
+        parent = Some(id);
+    }
+    parent
+}
+
+/// `20250110T093000Z` as a timestamp.
+fn timestamp(value: &str) -> Result<i64, String> {
+    NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+        .map(|datetime| datetime.and_utc().timestamp_millis())
+        .map_err(|_| format!("`{value}` is not a Taskwarrior date"))
+}
+
+/// The local day of a timestamp, as due dates are stored.
+fn day(millis: i64) -> Result<i64, String> {
+    dates::from_timestamp(millis)
+        .map(dates::to_timestamp)
+        .ok_or_else(|| String::from("date out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(value: &str) -> i64 {
+        timestamp(value).unwrap()
+    }
+
+    fn titles(workspaces: &[Workspace]) -> Vec<(&str, Option<usize>)> {
+        workspaces
+            .iter()
+            .map(|w| (w.title.as_str(), w.parent))
+            .collect()
+    }
+
+    #[test]
+    fn imports_an_export_array() {
+        let input = r#"[
+            {"uuid": "a1", "description": "Fix the fence", "status": "pending",
+             "entry": "20250110T093000Z", "due": "20250301T120000Z", "project": "Home.Garden",
+             "priority": "H", "tags": ["Outdoor", "outdoor", "weekend"],
+             "annotations": [
+                 {"entry": "20250111T080000Z", "description": "Ask the neighbours"},
+                 {"description": "Buy nails"}
+             ]},
+            {"uuid": "b2", "description": "  Pay the bills ", "status": "completed",
+             "entry": "20250102T100000Z", "end": "20250105T170000Z", "priority": "L",
+             "project": "Home", "urgency": 4.2},
+            {"uuid": "c3", "description": "Gone", "status": "deleted"},
+            {"uuid": "d4", "description": "Water the plants", "status": "recurring"},
+            {"description": "Call the bank", "status": "waiting", "priority": "X"}
+        ]"#;
+        let mut todos = vec![Todo::new(0, String::from("Already here"))];
+        let mut workspaces = Vec::new();
+        let report = import(input, &mut todos, &mut workspaces).unwrap();
+
+        assert_eq!(report.todos, [1, 2, 3]);
+        assert_eq!(
+            (report.deleted, report.recurring, report.existing),
+            (1, 1, 0)
+        );
+        assert_eq!(report.workspaces, ["Home", "Home/Garden"]);
+        assert_eq!(titles(&workspaces), [("Home", None), ("Garden", Some(0))]);
+
+        let fence = &todos[1];
+        assert_eq!(fence.text, "Fix the fence");
+        assert_eq!(fence.created_at, millis("20250110T093000Z"));
+        assert_eq!(fence.due, day(millis("20250301T120000Z")).ok());
+        assert_eq!(fence.priority, Some('A'));
+        assert_eq!(fence.tags, ["outdoor", "weekend"]);
+        assert_eq!(fence.workspace, Some(1));
+        let annotated = dates::from_timestamp(millis("20250111T080000Z")).unwrap();
+        assert_eq!(
+            fence.notes,
+            format!(
+                "{} Ask the neighbours\nBuy nails",
+                annotated.format("%Y-%m-%d")
+            )
+        );
+        assert_eq!(fence.fields, [(String::from("uuid"), String::from("a1"))]);
+
+        let bills = &todos[2];
+        assert_eq!(bills.text, "Pay the bills");
+        assert!(bills.done);
+        assert_eq!(bills.completed_at, Some(millis("20250105T170000Z")));
+        assert_eq!(bills.priority, Some('C'));
+        assert_eq!(bills.workspace, Some(0));
+
+        let bank = &todos[3];
+        assert!(!bank.done);
+        assert_eq!(bank.priority, None);
+        assert!(bank.fields.is_empty());
+    }
+
+    #[test]
+    fn imports_one_task_per_line_with_forward_dependencies() {
+        let input = "{\"uuid\": \"a1\", \"description\": \"Deploy\", \"status\": \"pending\", \
+                      \"depends\": \"b2,c3, zz\"}\n\
+                     \n\
+                     {\"uuid\": \"b2\", \"description\": \"Test\", \"status\": \"pending\", \
+                      \"depends\": [\"c3\"]}\n\
+                     {\"uuid\": \"c3\", \"description\": \"Build\", \"status\": \"pending\"}\n";
+        let mut todos = Vec::new();
+        let report = import(input, &mut todos, &mut Vec::new()).unwrap();
+        assert_eq!(report.todos, [0, 1, 2]);
+
+        let depends = |todo: &Todo| field(todo, "depends").map(str::to_string);
+        assert_eq!(depends(&todos[0]).as_deref(), Some("1,2"));
+        assert_eq!(depends(&todos[1]).as_deref(), Some("2"));
+        assert_eq!(depends(&todos[2]), None);
+
+        let err = import(
+            "{\"description\": \"Fine\", \"status\": \"pending\"}\n{oops}\n",
+            &mut todos,
+            &mut Vec::new(),
+        )
+        .err()
+        .unwrap();
+        assert!(err.starts_with("line 2: "), "{err}");
+    }
+
+    #[test]
+    fn skips_tasks_already_imported() {
+        let input = "{\"uuid\": \"a1\", \"description\": \"Fix the fence\", \
+                      \"status\": \"pending\", \"project\": \"Home.Garden\"}\n\
+                     {\"uuid\": \"b2\", \"description\": \"Mow\", \"status\": \"pending\", \
+                      \"project\": \"Home.Garden\", \"depends\": [\"a1\"]}\n";
+        let mut todos = Vec::new();
+        let mut workspaces = Vec::new();
+        import(input, &mut todos, &mut workspaces).unwrap();
+
+        let more = format!(
+            "{input}{{\"uuid\": \"c3\", \"description\": \"Rake\", \"status\": \"pending\", \
+             \"project\": \"Home.Garden\", \"depends\": [\"a1\"]}}\n"
+        );
+        let report = import(&more, &mut todos, &mut workspaces).unwrap();
+        assert_eq!(report.existing, 2);
+        assert_eq!(report.todos, [2]);
+        assert!(report.workspaces.is_empty());
+        assert_eq!(workspaces.len(), 2);
+        assert_eq!(todos[2].workspace, todos[0].workspace);
+        assert_eq!(field(&todos[2], "depends"), Some("0"));
+    }
+
+    #[test]
+    fn rejects_tasks_without_description() {
+        let input = r#"[{"uuid": "a1", "description": "Fine", "status": "pending"},
+                        {"uuid": "b2", "description": "  ", "status": "pending"}]"#;
+        let mut todos = Vec::new();
+        assert_eq!(
+            import(input, &mut todos, &mut Vec::new()).err().as_deref(),
+            Some("task b2 has no description")
+        );
+
+        let input = r#"[{"description": "Late", "status": "pending", "due": "tomorrow"}]"#;
+        assert_eq!(
+            import(input, &mut Vec::new(), &mut Vec::new())
+                .err()
+                .as_deref(),
+            Some("`tomorrow` is not a Taskwarrior date")
+        );
+    }
+}
@@ -0,0 +1,402 @@
+use chrono::NaiveDate;
+use itertools::Itertools;
+
+use crate::{
+    dates,
+    entities::{
+        todo::{self, Link},
+        workspace, Todo, Workspace,
+    },
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Writes one todo.txt line per todo, see <https://github.com/todotxt/todo.txt>.
+///
+/// The workspace becomes a `+project`, with whitespace in its title written as `-`,
+/// tags become `@context`s and the due date a `due:` field. Words of the text that look
+/// like one of these are escaped with a backslash, which import removes.
+///
+/// The format has no place for the rest: dates lose their time of day, notes are left
+/// out, and a workspace created by the import keeps the `-` of its project name.
+pub fn export(todos: &[Todo], workspaces: &[Workspace]) -> String {
+    todos
+        .iter()
+        .map(|todo| to_line(todo, workspaces) + "\n")
+        .collect()
+}
+
+fn to_line(todo: &Todo, workspaces: &[Workspace]) -> String {
+    let format = |millis: i64| dates::from_timestamp(millis).map(|d| d.format(DATE_FORMAT));
+    let mut words = Vec::new();
+
+    // A creation date can only follow a completion date, without one it becomes a field.
+    let created_field = todo.done && todo.completed_at.is_none();
+    if todo.done {
+        words.push(String::from("x"));
+        words.extend(todo.completed_at.and_then(format).map(|d| d.to_string()));
+    } else if let Some(priority) = todo.priority {
+        words.push(format!("({priority})"));
+    }
+    if !created_field {
+        words.extend(format(todo.created_at).map(|d| d.to_string()));
+    }
+    words.extend(todo.text.split_whitespace().map(escape));
+
+    if let Some(workspace) = todo
+        .workspace
+        .and_then(|id| workspaces.iter().find(|w| w.id == id))
+    {
+        words.push(format!("+{}", project_name(workspace)));
+    }
+    words.extend(todo.tags.iter().map(|tag| format!("@{tag}")));
+    words.extend(todo.due.and_then(format).map(|due| format!("due:{due}")));
+    if created_field {
+        words.extend(format(todo.created_at).map(|created| format!("created:{created}")));
+    }
+    if let (true, Some(priority)) = (todo.done, todo.priority) {
+        // Done tasks have no priority, todo.txt tools keep it in a field instead.
+        words.push(format!("pri:{priority}"));
+    }
+    words.extend(todo.link.as_ref().map(|link| format!("link:{link}")));
+    words.extend(
+        todo.fields
+            .iter()
+            .map(|(key, value)| format!("{key}:{value}")),
+    );
+
+    words.into_iter().filter(|word| !word.is_empty()).join(" ")
+}
+
+/// Prefixes the words of the text that would be read as a project, a context or a field
+/// with a backslash, e.g. `\@bob` or `\10:30`.
+fn escape(word: &str) -> String {
+    let token = |prefix: char| word.len() > 1 && word.starts_with(prefix);
+    match token('+') || token('@') || word.starts_with('\\') || parse_field(word).is_some() {
+        true => format!("\\{word}"),
+        false => word.to_string(),
+    }
+}
+
+fn project_name(workspace: &Workspace) -> String {
+    workspace.title.split_whitespace().join("-")
+}
+
+/// A todo.txt line split into its parts.
+#[derive(Default)]
+struct Line<'a> {
+    done: bool,
+    priority: Option<char>,
+    completed: Option<NaiveDate>,
+    created: Option<NaiveDate>,
+    text: Vec<&'a str>,
+    projects: Vec<&'a str>,
+    contexts: Vec<&'a str>,
+    due: Option<NaiveDate>,
+    link: Option<Link>,
+    fields: Vec<(&'a str, &'a str)>,
+}
+
+fn parse_line(line: &str) -> Line<'_> {
+    let date =
+        |word: Option<&&str>| word.and_then(|w| NaiveDate::parse_from_str(w, DATE_FORMAT).ok());
+    let mut parsed = Line::default();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let mut start = 0;
+
+    if words.first() == Some(&"x") {
+        parsed.done = true;
+        start = 1;
+        if let Some(completed) = date(words.get(start)) {
+            parsed.completed = Some(completed);
+            start += 1;
+        }
+    } else if let Some(priority) = words.first().and_then(|word| parse_priority(word)) {
+        parsed.priority = Some(priority);
+        start = 1;
+    }
+    if let Some(created) = date(words.get(start)) {
+        parsed.created = Some(created);
+        start += 1;
+    }
+
+    for word in words.into_iter().skip(start) {
+        if let Some(escaped) = word.strip_prefix('\\') {
+            parsed.text.push(escaped);
+        } else if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            parsed.projects.push(project);
+        } else if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            parsed.contexts.push(context);
+        } else if let Some((key, value)) = parse_field(word) {
+            match (key, NaiveDate::parse_from_str(value, DATE_FORMAT)) {
+                ("due", Ok(due)) => parsed.due = Some(due),
+                ("created", Ok(created)) if parsed.created.is_none() => {
+                    parsed.created = Some(created)
+                }
+                ("link", _) if parsed.link.is_none() => parsed.link = value.parse().ok(),
+                ("pri", _) if parsed.done && parsed.priority.is_none() => {
+                    match parse_priority(&format!("({value})")) {
+                        Some(priority) => parsed.priority = Some(priority),
+                        None => parsed.fields.push((key, value)),
+                    }
+                }
+                _ => parsed.fields.push((key, value)),
+            }
+        } else {
+            parsed.text.push(word);
+        }
+    }
+    parsed
+}
+
+fn parse_priority(word: &str) -> Option<char> {
+    match word.as_bytes() {
+        [b'(', priority, b')'] if priority.is_ascii_uppercase() => Some(*priority as char),
+        _ => None,
+    }
+}
+
+/// `key:value` with a non-empty value, URLs such as `https://…` are left in the text.
+fn parse_field(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once(':')?;
+    let valid_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    (valid_key && !value.is_empty() && !value.starts_with('/')).then_some((key, value))
+}
+
+/// Adds the todos of a todo.txt file, returning how many were read.
+///
+/// The first `+project` of a line picks the workspace, created when no workspace has that
+/// name. Further projects and `@context`s become tags.
+pub fn import(input: &str, todos: &mut Vec<Todo>, workspaces: &mut Vec<Workspace>) -> usize {
+    let mut count = 0;
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
+        let line = parse_line(line);
+        let mut todo = Todo::new(todo::next_id(todos), line.text.join(" "));
+        todo.done = line.done;
+        todo.priority = line.priority;
+        if let Some(created) = line.created {
+            todo.created_at = dates::to_timestamp(created);
+        }
+        todo.completed_at = line.completed.map(dates::to_timestamp);
+        todo.due = line.due.map(dates::to_timestamp);
+        todo.link = line.link;
+
+        let mut projects = line.projects.into_iter();
+        if let Some(project) = projects.next() {
+            todo.workspace = Some(find_or_add_workspace(workspaces, project));
+        }
+        for tag in projects.chain(line.contexts).map(todo::normalize_tag) {
+            if !todo.tags.contains(&tag) {
+                todo.tags.push(tag);
+            }
+        }
+        todo.fields = line
+            .fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        todos.push(todo);
+        count += 1;
+    }
+    count
+}
+
+fn find_or_add_workspace(workspaces: &mut Vec<Workspace>, project: &str) -> usize {
+    if let Some(workspace) = workspaces
+        .iter()
+        .find(|w| project_name(w).eq_ignore_ascii_case(project))
+    {
+        return workspace.id;
+    }
+    let id = workspace::next_id(workspaces);
+    workspaces.push(Workspace::new(id, project.to_string()));
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> i64 {
+        dates::to_timestamp(NaiveDate::parse_from_str(date, DATE_FORMAT).unwrap())
+    }
+
+    const HOUR: i64 = 60 * 60 * 1000;
+
+    fn todo(id: usize, text: &str) -> Todo {
+        let mut todo = Todo::new(id, text.to_string());
+        todo.created_at = date("2024-12-14") + 9 * HOUR;
+        todo
+    }
+
+    fn store() -> (Vec<Todo>, Vec<Workspace>) {
+        let workspaces = vec![
+            Workspace::new(0, String::from("infra")),
+            Workspace::new(1, String::from("Home office")),
+        ];
+
+        let mut deploy = todo(0, "Deploy the api");
+        deploy.priority = Some('A');
+        deploy.workspace = Some(0);
+        deploy.tags = vec![String::from("ops"), String::from("urgent")];
+        deploy.due = Some(date("2025-01-10"));
+        deploy.fields = vec![(String::from("rec"), String::from("1w"))];
+        deploy.link = Some(Link {
+            path: String::from("src/main.rs"),
+            line: Some(412),
+        });
+        deploy.notes = String::from("Ask before the freeze");
+
+        let mut milk = todo(1, "Buy milk");
+        milk.done = true;
+        milk.completed_at = Some(date("2024-12-20") + 17 * HOUR);
+        milk.priority = Some('C');
+        milk.workspace = Some(1);
+
+        let plain = todo(2, "Read https://example.com/post");
+
+        (vec![deploy, milk, plain], workspaces)
+    }
+
+    /// Compares what todo.txt holds, dates without their time of day.
+    fn assert_same(a: &Todo, b: &Todo) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.done, b.done);
+        assert_eq!(a.text, b.text);
+        assert_eq!(
+            dates::from_timestamp(a.created_at),
+            dates::from_timestamp(b.created_at)
+        );
+        assert_eq!(
+            a.completed_at.and_then(dates::from_timestamp),
+            b.completed_at.and_then(dates::from_timestamp)
+        );
+        assert_eq!(a.priority, b.priority);
+        assert_eq!(a.workspace, b.workspace);
+        assert_eq!(a.tags, b.tags);
+        assert_eq!(a.due, b.due);
+        assert_eq!(a.fields, b.fields);
+        assert_eq!(a.link, b.link);
+    }
+
+    #[test]
+    fn export_writes_todo_txt_lines() {
+        let (todos, workspaces) = store();
+        assert_eq!(
+            export(&todos, &workspaces),
+            "(A) 2024-12-14 Deploy the api +infra @ops @urgent due:2025-01-10 \
+             link:src/main.rs:412 rec:1w\n\
+             x 2024-12-20 2024-12-14 Buy milk +Home-office pri:C\n\
+             2024-12-14 Read https://example.com/post\n"
+        );
+    }
+
+    #[test]
+    fn export_then_import_keeps_what_todo_txt_holds() {
+        let (todos, workspaces) = store();
+        let exported = export(&todos, &workspaces);
+
+        let mut imported = Vec::new();
+        let mut imported_workspaces = Vec::new();
+        assert_eq!(
+            import(&exported, &mut imported, &mut imported_workspaces),
+            todos.len()
+        );
+
+        assert_eq!(imported.len(), todos.len());
+        for (original, imported) in todos.iter().zip(&imported) {
+            assert_same(original, imported);
+        }
+        assert_eq!(export(&imported, &imported_workspaces), exported);
+
+        // Lost on the way: the time of day, the notes and the spaces of new workspaces.
+        assert_eq!(imported[0].created_at, date("2024-12-14"));
+        assert_eq!(imported[1].completed_at, Some(date("2024-12-20")));
+        assert_eq!(imported[0].notes, "");
+        let titles = imported_workspaces
+            .iter()
+            .map(|w| w.title.as_str())
+            .collect_vec();
+        assert_eq!(titles, ["infra", "Home-office"]);
+    }
+
+    #[test]
+    fn import_reuses_existing_workspaces() {
+        let (todos, mut workspaces) = store();
+        let exported = export(&todos, &workspaces);
+
+        let mut imported = Vec::new();
+        import(&exported, &mut imported, &mut workspaces);
+        assert_eq!(workspaces.len(), 2);
+        assert_eq!(imported[0].workspace, Some(0));
+        assert_eq!(imported[1].workspace, Some(1));
+    }
+
+    #[test]
+    fn import_reads_the_todo_txt_examples() {
+        let input = "x 2011-03-02 2011-03-01 Review Tim's pull request +TodoTxtTouch @github\n\
+                     \n\
+                     (A) Thank Mom for the meatballs @phone\n\
+                     (B) Schedule Goodwill pickup +GarageSale @phone due:2011-03-05 rec:1w\n\
+                     Post signs around the neighborhood +GarageSale +Errands\n";
+        let mut todos = Vec::new();
+        let mut workspaces = Vec::new();
+        assert_eq!(import(input, &mut todos, &mut workspaces), 4);
+
+        assert!(todos[0].done);
+        assert_eq!(todos[0].text, "Review Tim's pull request");
+        assert_eq!(todos[0].completed_at, Some(date("2011-03-02")));
+        assert_eq!(todos[0].created_at, date("2011-03-01"));
+        assert_eq!(todos[0].tags, ["github"]);
+
+        assert_eq!(todos[1].priority, Some('A'));
+        assert_eq!(todos[1].workspace, None);
+
+        assert_eq!(todos[2].due, Some(date("2011-03-05")));
+        assert_eq!(todos[2].fields, [(String::from("rec"), String::from("1w"))]);
+
+        assert_eq!(todos[3].workspace, todos[2].workspace);
+        assert_eq!(todos[3].tags, ["errands"]);
+        let titles = workspaces.iter().map(|w| w.title.as_str()).collect_vec();
+        assert_eq!(titles, ["TodoTxtTouch", "GarageSale"]);
+    }
+
+    #[test]
+    fn text_that_looks_like_tokens_survives_the_round_trip() {
+        let (_, workspaces) = store();
+        let mut standup = todo(0, "Standup at 10:30 with @bob re: C++ +1 \\o/");
+        standup.workspace = Some(1);
+        let mut done = todo(1, "+ and @ alone");
+        done.done = true;
+        let todos = vec![standup, done];
+
+        let exported = export(&todos, &workspaces);
+        assert_eq!(
+            exported,
+            "2024-12-14 Standup at \\10:30 with \\@bob re: C++ \\+1 \\\\o/ +Home-office\n\
+             x + and @ alone created:2024-12-14\n"
+        );
+
+        let mut imported = Vec::new();
+        let mut imported_workspaces = workspaces.clone();
+        import(&exported, &mut imported, &mut imported_workspaces);
+        for (original, imported) in todos.iter().zip(&imported) {
+            assert_same(original, imported);
+        }
+        assert_eq!(imported_workspaces.len(), workspaces.len());
+    }
+
+    #[test]
+    fn priority_only_at_the_start_of_open_tasks() {
+        let mut todos = Vec::new();
+        import("Call (A) later\nx (A) done\n", &mut todos, &mut Vec::new());
+        assert_eq!(todos[0].priority, None);
+        assert_eq!(todos[0].text, "Call (A) later");
+        assert_eq!(todos[1].priority, None);
+        assert_eq!(todos[1].text, "(A) done");
+    }
+}
@@ -0,0 +1,465 @@
+use std::ops::Range;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Position, Rect},
+    text::Line,
+    widgets::Paragraph,
+    Frame,
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// A line of editable text, shared by the popups.
+///
+/// Besides typing and Backspace it handles Delete, Home/End, Ctrl-w, Ctrl-u and Ctrl-k,
+/// Alt-Left/Right to move by word and Up/Down to browse the lines submitted before.
+/// When drawn on several lines the text wraps and Up/Down first move between its lines.
+#[derive(Default)]
+pub struct TextInput {
+    value: String,
+    // Byte offset of the cursor in `value`, always on a char boundary
+    cursor: usize,
+    history: Vec<String>,
+    // Position in `history` of the line shown while browsing it
+    history_index: Option<usize>,
+    // What was typed before browsing the history, restored when going past its end
+    draft: String,
+    // First visible line when wrapped, first visible column otherwise
+    scroll: usize,
+    // Width the text was wrapped at when last drawn on several lines
+    wrap_width: Option<usize>,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Replaces the text, with the cursor at its end.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+        self.cursor = self.value.len();
+    }
+
+    /// Columns taken by the text before the cursor, wide characters counting twice.
+    pub fn cursor_column(&self) -> u16 {
+        self.value[..self.cursor].width() as u16
+    }
+
+    /// Edits the text for a key, returning false when the key does nothing here.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('w') if ctrl => self.delete_word_before(),
+            KeyCode::Char('u') if ctrl => self.delete_to_start(),
+            KeyCode::Char('k') if ctrl => self.delete_to_end(),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => self.insert_char(c),
+            KeyCode::Backspace => self.delete_before(),
+            KeyCode::Delete => self.delete_after(),
+            KeyCode::Left if alt || ctrl => self.move_word_left(),
+            KeyCode::Right if alt || ctrl => self.move_word_right(),
+            KeyCode::Left => self.cursor = self.previous_boundary(),
+            KeyCode::Right => self.cursor = self.next_boundary(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.value.len(),
+            KeyCode::Up => {
+                if !self.move_line(-1) {
+                    self.history_previous();
+                }
+            }
+            KeyCode::Down => {
+                if !self.move_line(1) {
+                    self.history_next();
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Inserts pasted text, line breaks becoming spaces.
+    pub fn paste(&mut self, text: &str) {
+        let text: String = text
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .map(|c| match c {
+                '\r' | '\n' | '\t' => ' ',
+                c => c,
+            })
+            .collect();
+        self.value.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Adds a submitted line to the history, which Up and Down go through.
+    pub fn remember(&mut self, line: &str) {
+        if !line.trim().is_empty() && self.history.last().is_none_or(|last| last != line) {
+            self.history.push(line.to_string());
+        }
+        self.history_index = None;
+    }
+
+    /// Draws the text in `area` and puts the terminal cursor on it.
+    ///
+    /// In an area of several lines the text wraps at spaces and scrolls down to keep the
+    /// cursor visible, in a single line it scrolls sideways instead.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let width = area.width as usize;
+        let (lines, x, y) = if area.height == 1 {
+            self.wrap_width = None;
+            let column = self.cursor_column() as usize;
+            self.scroll = scroll_to(self.scroll, column, width);
+            let line = self.visible_columns(self.scroll, width);
+            (vec![Line::from(line)], column - self.scroll, 0)
+        } else {
+            self.wrap_width = Some(width);
+            let ranges = self.wrap(width);
+            let (row, column) = self.cursor_row_column(&ranges, width);
+            self.scroll = scroll_to(self.scroll, row, area.height as usize);
+            let lines = ranges
+                .into_iter()
+                .skip(self.scroll)
+                .map(|range| Line::from(&self.value[range]))
+                .collect();
+            (lines, column, row - self.scroll)
+        };
+        frame.render_widget(Paragraph::new(lines), area);
+        frame.set_cursor_position(Position::new(area.x + x as u16, area.y + y as u16));
+    }
+
+    /// Byte ranges of the lines of the text wrapped at `width` columns.
+    ///
+    /// Lines break after the last space that fits, words longer than a line are cut and
+    /// spaces may hang past the end of a line.
+    fn wrap(&self, width: usize) -> Vec<Range<usize>> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        let mut column = 0;
+        let mut after_space = None;
+        for (idx, c) in self.value.char_indices() {
+            let char_width = c.width().unwrap_or_default();
+            if c != ' ' && column + char_width > width && idx > start {
+                let end = after_space.filter(|end| *end > start).unwrap_or(idx);
+                lines.push(start..end);
+                start = end;
+                column = self.value[start..idx].width();
+                after_space = None;
+            }
+            column += char_width;
+            if c == ' ' {
+                after_space = Some(idx + 1);
+            }
+        }
+        lines.push(start..self.value.len());
+        lines
+    }
+
+    /// Line and column of the cursor in the wrapped text, past the last line when the
+    /// cursor is at the end of a full line.
+    fn cursor_row_column(&self, lines: &[Range<usize>], width: usize) -> (usize, usize) {
+        let row = lines
+            .iter()
+            .rposition(|line| line.start <= self.cursor)
+            .unwrap_or_default();
+        let column = self.value[lines[row].start..self.cursor].width();
+        match column >= width {
+            true => (row + 1, 0),
+            false => (row, column),
+        }
+    }
+
+    /// Moves the cursor `delta` lines of the wrapped text up or down, keeping its column.
+    ///
+    /// Returns false when the text is not wrapped or there is no such line.
+    fn move_line(&mut self, delta: isize) -> bool {
+        let Some(width) = self.wrap_width else {
+            return false;
+        };
+        let lines = self.wrap(width);
+        let (row, column) = self.cursor_row_column(&lines, width);
+        let Some(line) = row.checked_add_signed(delta).and_then(|row| lines.get(row)) else {
+            return false;
+        };
+
+        let mut position = line.start;
+        let mut line_column = 0;
+        for (idx, c) in self.value[line.clone()].char_indices() {
+            let char_width = c.width().unwrap_or_default();
+            if line_column + char_width > column {
+                break;
+            }
+            line_column += char_width;
+            position = line.start + idx + c.len_utf8();
+        }
+        // The end of a line is the start of the next one, stay on the line instead.
+        if position == line.end && line.end < self.value.len() && position > line.start {
+            position = self.boundary_before(position);
+        }
+        self.cursor = position;
+        true
+    }
+
+    /// The characters drawn between columns `from` and `from + width`.
+    fn visible_columns(&self, from: usize, width: usize) -> String {
+        let mut column = 0;
+        let mut visible = String::new();
+        for c in self.value.chars() {
+            let char_width = c.width().unwrap_or_default();
+            if column >= from && column + char_width <= from + width {
+                visible.push(c);
+            }
+            column += char_width;
+        }
+        visible
+    }
+
+    fn insert_char(&mut self, c: char) {
+        self.value.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    fn delete_before(&mut self) {
+        let start = self.previous_boundary();
+        self.value.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    fn delete_after(&mut self) {
+        let end = self.next_boundary();
+        self.value.replace_range(self.cursor..end, "");
+    }
+
+    /// Deletes back to the start of the previous whitespace separated word, like Ctrl-w in a shell.
+    fn delete_word_before(&mut self) {
+        let before = self.value[..self.cursor].trim_end();
+        let start = before
+            .rfind(char::is_whitespace)
+            .map_or(0, |idx| next_char(before, idx));
+        self.value.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    fn delete_to_start(&mut self) {
+        self.value.replace_range(..self.cursor, "");
+        self.cursor = 0;
+    }
+
+    fn delete_to_end(&mut self) {
+        self.value.truncate(self.cursor);
+    }
+
+    /// Moves to the start of the word before the cursor, words being runs of alphanumerics.
+    fn move_word_left(&mut self) {
+        let before = &self.value[..self.cursor];
+        let end = before
+            .rfind(char::is_alphanumeric)
+            .map_or(0, |idx| next_char(before, idx));
+        self.cursor = before[..end]
+            .rfind(|c: char| !c.is_alphanumeric())
+            .map_or(0, |idx| next_char(before, idx));
+    }
+
+    /// Moves to the end of the word after the cursor.
+    fn move_word_right(&mut self) {
+        let after = &self.value[self.cursor..];
+        let start = after.find(char::is_alphanumeric).unwrap_or(after.len());
+        let end = after[start..]
+            .find(|c: char| !c.is_alphanumeric())
+            .map_or(after.len(), |idx| start + idx);
+        self.cursor += end;
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.boundary_before(self.cursor)
+    }
+
+    /// Start of the character before `position`, together with the combining marks after it.
+    fn boundary_before(&self, position: usize) -> usize {
+        let mut boundary = position;
+        for (idx, c) in self.value[..position].char_indices().rev() {
+            boundary = idx;
+            if !is_combining(c) {
+                break;
+            }
+        }
+        boundary
+    }
+
+    /// End of the character after the cursor, together with the combining marks after it.
+    fn next_boundary(&self) -> usize {
+        let after = &self.value[self.cursor..];
+        let end = after
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| !is_combining(*c))
+            .map_or(after.len(), |(idx, _)| idx);
+        self.cursor + end
+    }
+
+    fn history_previous(&mut self) {
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.value.clone();
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(index);
+        self.set_value(self.history[index].clone());
+    }
+
+    fn history_next(&mut self) {
+        let Some(index) = self.history_index else {
+            return;
+        };
+        match self.history.get(index + 1) {
+            Some(line) => {
+                self.history_index = Some(index + 1);
+                self.set_value(line.clone());
+            }
+            None => {
+                self.history_index = None;
+                let draft = std::mem::take(&mut self.draft);
+                self.set_value(draft);
+            }
+        }
+    }
+}
+
+/// Moves a scroll offset the least needed to show `position` in a view of `size`.
+fn scroll_to(scroll: usize, position: usize, size: usize) -> usize {
+    if position < scroll {
+        position
+    } else if position >= scroll + size {
+        position + 1 - size
+    } else {
+        scroll
+    }
+}
+
+/// Byte offset of the character after the one starting at `idx`.
+fn next_char(text: &str, idx: usize) -> usize {
+    idx + text[idx..].chars().next().map_or(0, char::len_utf8)
+}
+
+/// Zero width characters drawn on top of the one before them, e.g. U+0301 in `é`.
+fn is_combining(c: char) -> bool {
+    c.width() == Some(0) && !c.is_control()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut TextInput, code: KeyCode, modifiers: KeyModifiers) {
+        input.handle_key(KeyEvent::new(code, modifiers));
+    }
+
+    fn typed(text: &str) -> TextInput {
+        let mut input = TextInput::new();
+        input.paste(text);
+        input
+    }
+
+    #[test]
+    fn deletes_words_and_lines() {
+        let mut input = typed("deploy the api  ");
+        press(&mut input, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(input.value(), "deploy the ");
+
+        press(&mut input, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Right, KeyModifiers::ALT);
+        press(&mut input, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(input.value(), "deploy");
+
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(input.value(), "y");
+    }
+
+    #[test]
+    fn moves_by_word() {
+        let mut input = typed("fix #42, then ship");
+        press(&mut input, KeyCode::Left, KeyModifiers::ALT);
+        assert_eq!(input.cursor_column(), 14);
+        press(&mut input, KeyCode::Left, KeyModifiers::ALT);
+        press(&mut input, KeyCode::Left, KeyModifiers::ALT);
+        assert_eq!(input.cursor_column(), 5);
+        press(&mut input, KeyCode::Right, KeyModifiers::ALT);
+        assert_eq!(input.cursor_column(), 7);
+    }
+
+    #[test]
+    fn places_the_cursor_after_wide_and_combining_characters() {
+        let mut input = typed("日本e\u{301}");
+        assert_eq!(input.cursor_column(), 5);
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(input.cursor_column(), 4);
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(input.cursor_column(), 2);
+        press(&mut input, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(input.value(), "日e\u{301}");
+        press(&mut input, KeyCode::End, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(input.value(), "日");
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        let input = typed("buy milk and supercalifragilistic");
+        let lines: Vec<&str> = input
+            .wrap(10)
+            .into_iter()
+            .map(|range| &input.value[range])
+            .collect();
+        assert_eq!(lines, ["buy milk ", "and ", "supercalif", "ragilistic"]);
+        // The line is full, the cursor goes to the start of the next one.
+        assert_eq!(input.cursor_row_column(&input.wrap(10), 10), (4, 0));
+    }
+
+    #[test]
+    fn moves_between_wrapped_lines() {
+        let mut input = typed("first line second");
+        input.wrap_width = Some(11);
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(input.cursor_row_column(&input.wrap(11), 11), (0, 6));
+        press(&mut input, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(input.cursor, input.value.len());
+    }
+
+    #[test]
+    fn scrolls_sideways_to_the_cursor() {
+        let input = typed("日本語のテキスト");
+        assert_eq!(input.visible_columns(4, 5), "語の");
+        assert_eq!(scroll_to(0, 16, 10), 7);
+        assert_eq!(scroll_to(7, 3, 10), 3);
+        assert_eq!(scroll_to(3, 8, 10), 3);
+    }
+
+    #[test]
+    fn browses_the_history() {
+        let mut input = TextInput::new();
+        input.remember("first");
+        input.remember("second");
+        input.paste("draft");
+
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(input.value(), "second");
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(input.value(), "first");
+        press(&mut input, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(input.value(), "draft");
+    }
+}
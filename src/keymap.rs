@@ -0,0 +1,616 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use itertools::Itertools;
+
+use crate::palette::SortKey;
+
+/// Everything the interface can do, bound to keys or sent by the panes and popups.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    NextPane,
+    FocusStatus,
+    FocusInbox,
+    FocusWorkspaces,
+    FocusTodos,
+    Down,
+    Up,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    AddTodo,
+    DeleteTodo,
+    ToggleTodo,
+    NewWorkspace,
+    NewSubworkspace,
+    RenameWorkspace,
+    DeleteWorkspace,
+    MoveWorkspaceDown,
+    MoveWorkspaceUp,
+    IndentWorkspace,
+    OutdentWorkspace,
+    CollapseWorkspace,
+    ExpandWorkspace,
+    ToggleSubworkspaces,
+    CycleWorkspaceColor,
+    ActivateWorkspace,
+    NextTab,
+    PreviousTab,
+    Help,
+    CommandPalette,
+    ArchiveDone,
+    CyclePalette,
+    CycleTheme,
+    PromoteTodo,
+    OpenLink,
+    // The actions below have no name, they are sent by panes and popups.
+    /// Quits without asking for confirmation.
+    Exit,
+    /// Closes a popup without doing anything.
+    Cancel,
+    RunCommand(String),
+    CreateTodo(String),
+    /// Moves the selected todo to the workspace best matching a title.
+    MoveTodo(String),
+    /// Sets the due date of the selected todo, `none` clears it.
+    SetDue(String),
+    SortTodos(SortKey),
+    /// Writes the store to a file, in the format of its extension.
+    Export(String),
+    CreateWorkspace {
+        title: String,
+        parent: Option<usize>,
+    },
+    RenameWorkspaceTo {
+        id: usize,
+        title: String,
+    },
+}
+
+// Names used for actions in the config file, and the labels shown in the footer and help.
+pub const ACTIONS: &[(Action, &str, &str)] = &[
+    (Action::Quit, "quit", "Quit"),
+    (Action::NextPane, "next_pane", "Next pane"),
+    (Action::FocusStatus, "focus_status", "Status"),
+    (Action::FocusInbox, "focus_inbox", "Inbox"),
+    (Action::FocusWorkspaces, "focus_workspaces", "Workspaces"),
+    (Action::FocusTodos, "focus_todos", "Todos"),
+    (Action::Down, "down", "Down"),
+    (Action::Up, "up", "Up"),
+    (Action::Top, "top", "Top"),
+    (Action::Bottom, "bottom", "Bottom"),
+    (Action::Left, "left", "Previous column"),
+    (Action::Right, "right", "Next column"),
+    (Action::AddTodo, "add_todo", "Add"),
+    (Action::DeleteTodo, "delete_todo", "Delete"),
+    (Action::ToggleTodo, "toggle_todo", "Done"),
+    (Action::NewWorkspace, "new_workspace", "New"),
+    (Action::NewSubworkspace, "new_subworkspace", "New child"),
+    (Action::RenameWorkspace, "rename_workspace", "Rename"),
+    (Action::DeleteWorkspace, "delete_workspace", "Delete"),
+    (
+        Action::MoveWorkspaceDown,
+        "move_workspace_down",
+        "Move down",
+    ),
+    (Action::MoveWorkspaceUp, "move_workspace_up", "Move up"),
+    (Action::IndentWorkspace, "indent_workspace", "Indent"),
+    (Action::OutdentWorkspace, "outdent_workspace", "Outdent"),
+    (Action::CollapseWorkspace, "collapse_workspace", "Collapse"),
+    (Action::ExpandWorkspace, "expand_workspace", "Expand"),
+    (
+        Action::ToggleSubworkspaces,
+        "toggle_subworkspaces",
+        "Include children",
+    ),
+    (
+        Action::CycleWorkspaceColor,
+        "cycle_workspace_color",
+        "Colour",
+    ),
+    (Action::ActivateWorkspace, "activate_workspace", "Activate"),
+    (Action::NextTab, "next_tab", "Next tab"),
+    (Action::PreviousTab, "previous_tab", "Previous tab"),
+    (Action::Help, "help", "Help"),
+    (Action::CommandPalette, "command_palette", "Commands"),
+    (Action::ArchiveDone, "archive_done", "Archive done"),
+    (Action::CyclePalette, "cycle_palette", "Switch palette"),
+    (Action::CycleTheme, "cycle_theme", "Theme"),
+    (Action::PromoteTodo, "promote_todo", "Promote"),
+    (Action::OpenLink, "open_link", "Open link"),
+];
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        ACTIONS
+            .iter()
+            .find(|(action, _, _)| action == self)
+            .map(|(_, name, _)| *name)
+            .unwrap_or_default()
+    }
+
+    pub fn label(&self) -> &'static str {
+        ACTIONS
+            .iter()
+            .find(|(action, _, _)| action == self)
+            .map(|(_, _, label)| *label)
+            .unwrap_or_default()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS
+            .iter()
+            .find(|(_, n, _)| *n == name)
+            .map(|(action, _, _)| action.clone())
+    }
+}
+
+/// Where a binding applies. Pane bindings take precedence over global ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Context {
+    Global,
+    Status,
+    Inbox,
+    Workspaces,
+    Todos,
+}
+
+impl Context {
+    pub const ALL: [Context; 5] = [
+        Context::Global,
+        Context::Status,
+        Context::Inbox,
+        Context::Workspaces,
+        Context::Todos,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Context::Global => "global",
+            Context::Status => "status",
+            Context::Inbox => "inbox",
+            Context::Workspaces => "workspaces",
+            Context::Todos => "todos",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        let mut modifiers = event.modifiers;
+        // The case of a character already tells whether shift was held.
+        if let KeyCode::Char(_) = event.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self::new(event.code, modifiers)
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // A lone "-" is the minus key rather than an empty modifier list.
+        while let Some((modifier, tail)) = rest.split_once('-').filter(|(_, t)| !t.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" => modifiers |= KeyModifiers::CONTROL,
+                "alt" => modifiers |= KeyModifiers::ALT,
+                "shift" => modifiers |= KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{modifier}` in `{s}`")),
+            }
+            rest = tail;
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => {
+                        if modifiers.contains(KeyModifiers::SHIFT) {
+                            modifiers.remove(KeyModifiers::SHIFT);
+                            KeyCode::Char(c.to_ascii_uppercase())
+                        } else {
+                            KeyCode::Char(c)
+                        }
+                    }
+                    _ => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                        Some(n @ 1..=12) => KeyCode::F(n),
+                        _ => return Err(format!("unknown key `{s}`")),
+                    },
+                }
+            }
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+/// One or more keys pressed one after the other, written `g g` in the config.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<Key>);
+
+impl FromStr for KeySequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split_whitespace()
+            .map(Key::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err(String::from("empty key binding"));
+        }
+        Ok(Self(keys))
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, key) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{key}")?;
+        }
+        Ok(())
+    }
+}
+
+enum Lookup {
+    Action(Action),
+    Prefix,
+    None,
+}
+
+pub struct Keymap {
+    bindings: HashMap<Context, Vec<(KeySequence, Action)>>,
+    pending: Vec<Key>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: HashMap::new(),
+            pending: Vec::new(),
+        };
+        for (context, keys, action) in DEFAULT_BINDINGS {
+            let keys = keys.parse().expect("default bindings are valid");
+            keymap.bind(*context, keys, action.clone());
+        }
+        keymap
+    }
+}
+
+const DEFAULT_BINDINGS: &[(Context, &str, Action)] = &[
+    (Context::Global, "q", Action::Quit),
+    (Context::Global, "tab", Action::NextPane),
+    (Context::Global, "1", Action::FocusStatus),
+    (Context::Global, "2", Action::FocusInbox),
+    (Context::Global, "3", Action::FocusWorkspaces),
+    (Context::Global, "4", Action::FocusTodos),
+    (Context::Global, "?", Action::Help),
+    (Context::Global, ":", Action::CommandPalette),
+    (Context::Global, "ctrl-p", Action::CommandPalette),
+    (Context::Global, "t", Action::CycleTheme),
+    (Context::Inbox, "j", Action::Down),
+    (Context::Inbox, "k", Action::Up),
+    (Context::Inbox, "g g", Action::Top),
+    (Context::Inbox, "G", Action::Bottom),
+    (Context::Workspaces, "j", Action::Down),
+    (Context::Workspaces, "k", Action::Up),
+    (Context::Workspaces, "g g", Action::Top),
+    (Context::Workspaces, "G", Action::Bottom),
+    (Context::Workspaces, "n", Action::NewWorkspace),
+    (Context::Workspaces, "N", Action::NewSubworkspace),
+    (Context::Workspaces, "r", Action::RenameWorkspace),
+    (Context::Workspaces, "d", Action::DeleteWorkspace),
+    (Context::Workspaces, "J", Action::MoveWorkspaceDown),
+    (Context::Workspaces, "K", Action::MoveWorkspaceUp),
+    (Context::Workspaces, ">", Action::IndentWorkspace),
+    (Context::Workspaces, "<", Action::OutdentWorkspace),
+    (Context::Workspaces, "h", Action::CollapseWorkspace),
+    (Context::Workspaces, "l", Action::ExpandWorkspace),
+    (Context::Workspaces, "s", Action::ToggleSubworkspaces),
+    (Context::Workspaces, "c", Action::CycleWorkspaceColor),
+    (Context::Workspaces, "enter", Action::ActivateWorkspace),
+    (Context::Workspaces, "]", Action::NextTab),
+    (Context::Workspaces, "[", Action::PreviousTab),
+    (Context::Todos, "j", Action::Down),
+    (Context::Todos, "down", Action::Down),
+    (Context::Todos, "k", Action::Up),
+    (Context::Todos, "up", Action::Up),
+    (Context::Todos, "g g", Action::Top),
+    (Context::Todos, "G", Action::Bottom),
+    (Context::Todos, "a", Action::AddTodo),
+    (Context::Todos, "d", Action::DeleteTodo),
+    (Context::Todos, "space", Action::ToggleTodo),
+    (Context::Todos, "X", Action::ArchiveDone),
+    (Context::Todos, "p", Action::PromoteTodo),
+    (Context::Todos, "o", Action::OpenLink),
+    (Context::Todos, "h", Action::Left),
+    (Context::Todos, "left", Action::Left),
+    (Context::Todos, "l", Action::Right),
+    (Context::Todos, "right", Action::Right),
+];
+
+impl Keymap {
+    fn bind(&mut self, context: Context, keys: KeySequence, action: Action) {
+        self.bindings
+            .entry(context)
+            .or_default()
+            .push((keys, action));
+    }
+
+    /// Replaces the bindings of `action` in `context` with `keys`.
+    pub fn rebind(&mut self, context: Context, action: Action, keys: Vec<KeySequence>) {
+        let bindings = self.bindings.entry(context).or_default();
+        bindings.retain(|(_, a)| *a != action);
+        bindings.extend(keys.into_iter().map(|keys| (keys, action.clone())));
+    }
+
+    /// Bindings of a context grouped by action, in the order they were defined.
+    pub fn bindings(&self, context: Context) -> Vec<(Action, Vec<&KeySequence>)> {
+        let mut grouped: Vec<(Action, Vec<&KeySequence>)> = Vec::new();
+        for (keys, action) in self.bindings.get(&context).into_iter().flatten() {
+            match grouped.iter_mut().find(|(a, _)| a == action) {
+                Some((_, all_keys)) => all_keys.push(keys),
+                None => grouped.push((action.clone(), vec![keys])),
+            }
+        }
+        grouped
+    }
+
+    /// Every key sequence bound to `action`, in any context.
+    pub fn keys_for(&self, action: &Action) -> Vec<&KeySequence> {
+        Context::ALL
+            .iter()
+            .filter_map(|context| self.bindings.get(context))
+            .flatten()
+            .filter(|(_, a)| a == action)
+            .map(|(keys, _)| keys)
+            .unique()
+            .collect()
+    }
+
+    /// Lists every binding that can never be triggered as configured.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for context in Context::ALL {
+            let Some(bindings) = self.bindings.get(&context) else {
+                continue;
+            };
+            for (i, (keys, action)) in bindings.iter().enumerate() {
+                for (other_keys, other_action) in &bindings[i + 1..] {
+                    if action == other_action {
+                        continue;
+                    }
+                    if keys == other_keys {
+                        errors.push(format!(
+                            "[keys.{}] `{keys}` is bound to both `{}` and `{}`",
+                            context.name(),
+                            action.name(),
+                            other_action.name()
+                        ));
+                    } else if keys.0.starts_with(&other_keys.0) || other_keys.0.starts_with(&keys.0)
+                    {
+                        errors.push(format!(
+                            "[keys.{}] `{keys}` (`{}`) and `{other_keys}` (`{}`) overlap, one of them can never be typed",
+                            context.name(),
+                            action.name(),
+                            other_action.name()
+                        ));
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    fn lookup(&self, context: Context, keys: &[Key]) -> Lookup {
+        let mut prefix = false;
+        for context in [context, Context::Global] {
+            for (sequence, action) in self.bindings.get(&context).into_iter().flatten() {
+                if sequence.0 == keys {
+                    return Lookup::Action(action.clone());
+                }
+                prefix |= sequence.0.starts_with(keys);
+            }
+            // A pane that binds the first keys of a sequence shadows the global bindings.
+            if prefix {
+                return Lookup::Prefix;
+            }
+        }
+        Lookup::None
+    }
+
+    /// Feeds a key press, returning the action once a whole sequence has been typed.
+    pub fn handle(&mut self, context: Context, event: KeyEvent) -> Option<Action> {
+        let key = Key::from(event);
+        self.pending.push(key);
+
+        match self.lookup(context, &self.pending) {
+            Lookup::Action(action) => {
+                self.pending.clear();
+                Some(action)
+            }
+            Lookup::Prefix => None,
+            Lookup::None if self.pending.len() > 1 => {
+                // The key did not continue the sequence, so try it on its own.
+                self.pending.clear();
+                self.handle(context, event)
+            }
+            Lookup::None => {
+                self.pending.clear();
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(s: &str) -> Key {
+        s.parse().unwrap()
+    }
+
+    fn sequence(s: &str) -> KeySequence {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_keys() {
+        assert_eq!(
+            key("ctrl-p"),
+            Key::new(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            key("Ctrl-Alt-x"),
+            Key::new(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+        assert_eq!(key("shift-g"), key("G"));
+        assert_eq!(key("-"), Key::new(KeyCode::Char('-'), KeyModifiers::NONE));
+        assert_eq!(
+            key("space"),
+            Key::new(KeyCode::Char(' '), KeyModifiers::NONE)
+        );
+        assert_eq!(key("F5"), Key::new(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(sequence("g g").0, vec![key("g"), key("g")]);
+        assert_eq!(sequence("ctrl-x  enter").to_string(), "ctrl-x enter");
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert_eq!(
+            "hyper-x".parse::<Key>(),
+            Err(String::from("unknown modifier `hyper` in `hyper-x`"))
+        );
+        assert_eq!("f13".parse::<Key>(), Err(String::from("unknown key `f13`")));
+        assert!("enter2".parse::<Key>().is_err());
+        assert!("g gg".parse::<KeySequence>().is_err());
+        assert_eq!(
+            " ".parse::<KeySequence>(),
+            Err(String::from("empty key binding"))
+        );
+    }
+
+    #[test]
+    fn default_bindings_do_not_conflict() {
+        assert!(Keymap::default().validate().is_empty());
+    }
+
+    #[test]
+    fn reports_keys_bound_twice_in_a_context() {
+        let mut keymap = Keymap::default();
+        keymap.rebind(Context::Todos, Action::AddTodo, vec![sequence("d")]);
+        assert_eq!(
+            keymap.validate(),
+            vec!["[keys.todos] `d` is bound to both `delete_todo` and `add_todo`"]
+        );
+
+        // The same keys in different contexts are fine, the pane wins.
+        let mut keymap = Keymap::default();
+        keymap.rebind(Context::Inbox, Action::Top, vec![sequence("d")]);
+        assert!(keymap.validate().is_empty());
+    }
+
+    #[test]
+    fn reports_sequences_starting_with_another_binding() {
+        let mut keymap = Keymap::default();
+        keymap.rebind(Context::Todos, Action::AddTodo, vec![sequence("g")]);
+        let errors = keymap.validate();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("[keys.todos] `g g` (`top`) and `g` (`add_todo`) overlap"));
+    }
+
+    #[test]
+    fn handles_sequences() {
+        let mut keymap = Keymap::default();
+        let press = |keymap: &mut Keymap, c| {
+            keymap.handle(
+                Context::Todos,
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+            )
+        };
+        assert_eq!(press(&mut keymap, 'g'), None);
+        assert_eq!(press(&mut keymap, 'g'), Some(Action::Top));
+        // A key that does not continue the sequence is taken on its own.
+        assert_eq!(press(&mut keymap, 'g'), None);
+        assert_eq!(press(&mut keymap, 'j'), Some(Action::Down));
+    }
+}
@@ -0,0 +1,20 @@
+//! Todos and workspaces as stored by lazytodo, with the import and export formats.
+//!
+//! The terminal interface and the command line live in the `lazytodo` binary, this
+//! library has no terminal dependencies so that other tools can read and write the store.
+
+pub mod dates;
+pub mod editor;
+pub mod entities;
+pub mod filter;
+pub mod formats;
+pub mod merge;
+pub mod scanner;
+pub mod stats;
+pub mod storage;
+pub mod store;
+pub mod sync;
+
+pub use entities::{todo::Link, Todo, Workspace};
+pub use filter::Filter;
+pub use store::Store;
@@ -0,0 +1,394 @@
+//! Three-way merge of the store, for when several lazytodo processes edit the same `.lazytodo`.
+//!
+//! Todos and workspaces are matched by id. A field changed on one side only takes that
+//! change, a field changed on both sides keeps ours, as ours is the one being written last.
+//! The order of the workspaces is merged the same way.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{Store, Todo, Workspace};
+
+/// A todo or a workspace, merged field by field.
+pub trait Record: Clone + PartialEq {
+    const KIND: &'static str;
+
+    fn id(&self) -> usize;
+
+    fn set_id(&mut self, id: usize);
+
+    /// Takes the fields `theirs` changed since `base`, returning the ones we changed too.
+    fn merge(&mut self, base: &Self, theirs: &Self) -> Vec<&'static str>;
+}
+
+impl Record for Todo {
+    const KIND: &'static str = "todo";
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn set_id(&mut self, id: usize) {
+        self.id = id;
+    }
+
+    fn merge(&mut self, base: &Self, theirs: &Self) -> Vec<&'static str> {
+        let mut conflicts = Vec::new();
+        let c = &mut conflicts;
+        // The completion date follows the done flag, so both come from the same side.
+        let mut done = (self.done, self.completed_at);
+        let (base_done, their_done) = (
+            (base.done, base.completed_at),
+            (theirs.done, theirs.completed_at),
+        );
+        field("done", &mut done, &base_done, &their_done, c);
+        (self.done, self.completed_at) = done;
+        field("text", &mut self.text, &base.text, &theirs.text, c);
+        field(
+            "created_at",
+            &mut self.created_at,
+            &base.created_at,
+            &theirs.created_at,
+            c,
+        );
+        field(
+            "workspace",
+            &mut self.workspace,
+            &base.workspace,
+            &theirs.workspace,
+            c,
+        );
+        field("due", &mut self.due, &base.due, &theirs.due, c);
+        field("tags", &mut self.tags, &base.tags, &theirs.tags, c);
+        field(
+            "priority",
+            &mut self.priority,
+            &base.priority,
+            &theirs.priority,
+            c,
+        );
+        field("fields", &mut self.fields, &base.fields, &theirs.fields, c);
+        field("link", &mut self.link, &base.link, &theirs.link, c);
+        field("notes", &mut self.notes, &base.notes, &theirs.notes, c);
+        conflicts
+    }
+}
+
+impl Record for Workspace {
+    const KIND: &'static str = "workspace";
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn set_id(&mut self, id: usize) {
+        self.id = id;
+    }
+
+    fn merge(&mut self, base: &Self, theirs: &Self) -> Vec<&'static str> {
+        let mut conflicts = Vec::new();
+        let c = &mut conflicts;
+        field("title", &mut self.title, &base.title, &theirs.title, c);
+        field("color", &mut self.color, &base.color, &theirs.color, c);
+        field("parent", &mut self.parent, &base.parent, &theirs.parent, c);
+        field(
+            "collapsed",
+            &mut self.collapsed,
+            &base.collapsed,
+            &theirs.collapsed,
+            c,
+        );
+        field("file", &mut self.file, &base.file, &theirs.file, c);
+        conflicts
+    }
+}
+
+/// Takes `theirs` when only they changed the field, noting a conflict when both sides did.
+fn field<T: Clone + PartialEq>(
+    name: &'static str,
+    ours: &mut T,
+    base: &T,
+    theirs: &T,
+    conflicts: &mut Vec<&'static str>,
+) {
+    if theirs == base || theirs == ours {
+        return;
+    }
+    match ours == base {
+        true => *ours = theirs.clone(),
+        false => conflicts.push(name),
+    }
+}
+
+/// Merges `ours` and `theirs`, two versions of the store that both started from `base`.
+///
+/// Returns the merged store and a description of each conflict.
+pub fn merge(base: &Store, ours: &Store, theirs: &Store) -> (Store, Vec<String>) {
+    let mut ours = ours.clone();
+
+    // Workspaces created on both sides may have taken the same id, ours move to a new one.
+    let moved = renumber(&base.workspaces, &mut ours.workspaces, &theirs.workspaces);
+    let new_id = |id: usize| moved.get(&id).copied().unwrap_or(id);
+    for workspace in &mut ours.workspaces {
+        workspace.parent = workspace.parent.map(new_id);
+    }
+    for todo in &mut ours.todos {
+        todo.workspace = todo.workspace.map(new_id);
+    }
+    renumber(&base.todos, &mut ours.todos, &theirs.todos);
+
+    let mut conflicts = Vec::new();
+    let ids = |list: &[Workspace]| list.iter().map(|w| w.id).collect::<Vec<_>>();
+    let orders = [
+        ids(&base.workspaces),
+        ids(&ours.workspaces),
+        ids(&theirs.workspaces),
+    ];
+    let mut workspaces = merge_records(
+        &base.workspaces,
+        ours.workspaces,
+        &theirs.workspaces,
+        &mut conflicts,
+    );
+    merge_order(&orders, &mut workspaces, &mut conflicts);
+    let todos = merge_records(&base.todos, ours.todos, &theirs.todos, &mut conflicts);
+    (Store { todos, workspaces }, conflicts)
+}
+
+/// Gives a new id to the records we created with an id they created too, returning the moves.
+fn renumber<T: Record>(base: &[T], ours: &mut [T], theirs: &[T]) -> HashMap<usize, usize> {
+    let base_ids: HashSet<usize> = base.iter().map(T::id).collect();
+    let mut next_id = base
+        .iter()
+        .chain(ours.iter())
+        .chain(theirs)
+        .map(|r| r.id() + 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut moved = HashMap::new();
+    for record in ours.iter_mut().filter(|r| !base_ids.contains(&r.id())) {
+        let taken = theirs.iter().any(|t| t.id() == record.id() && t != record);
+        if taken {
+            moved.insert(record.id(), next_id);
+            record.set_id(next_id);
+            next_id += 1;
+        }
+    }
+    moved
+}
+
+fn merge_records<T: Record>(
+    base: &[T],
+    ours: Vec<T>,
+    theirs: &[T],
+    conflicts: &mut Vec<String>,
+) -> Vec<T> {
+    let base: HashMap<usize, &T> = base.iter().map(|r| (r.id(), r)).collect();
+    let theirs_by_id: HashMap<usize, &T> = theirs.iter().map(|r| (r.id(), r)).collect();
+    let our_ids: HashSet<usize> = ours.iter().map(T::id).collect();
+    let kind = T::KIND;
+
+    let mut merged = Vec::new();
+    for mut record in ours {
+        let id = record.id();
+        match (base.get(&id), theirs_by_id.get(&id)) {
+            (Some(base), Some(theirs)) => {
+                let fields = record.merge(base, theirs);
+                if !fields.is_empty() {
+                    conflicts.push(format!("{kind} #{id}: {}", fields.join(", ")));
+                }
+                merged.push(record);
+            }
+            // Deleted by them, unless we changed it since.
+            (Some(base), None) if record == **base => {}
+            (Some(_), None) => {
+                conflicts.push(format!("{kind} #{id}: deleted by the other side"));
+                merged.push(record);
+            }
+            // Created by us, or by both sides with the same content.
+            (None, _) => merged.push(record),
+        }
+    }
+
+    // Deleted by us, their changes are lost as we write last.
+    for record in theirs.iter().filter(|r| !our_ids.contains(&r.id())) {
+        if base.get(&record.id()).is_some_and(|base| *base != record) {
+            let id = record.id();
+            conflicts.push(format!(
+                "{kind} #{id}: deleted while changed by the other side"
+            ));
+        }
+    }
+
+    // What they created goes after our records.
+    merged.extend(
+        theirs
+            .iter()
+            .filter(|r| !base.contains_key(&r.id()) && !our_ids.contains(&r.id()))
+            .cloned(),
+    );
+    merged
+}
+
+/// Puts the records in the order they moved them to, when we did not move them ourselves.
+///
+/// `orders` are the ids of the base, our and their records. Only the records on all three
+/// sides are moved, the others keep their place.
+fn merge_order<T: Record>(orders: &[Vec<usize>; 3], merged: &mut [T], conflicts: &mut Vec<String>) {
+    let in_all = |id: &usize| orders.iter().all(|order| order.contains(id));
+    let [base, ours, theirs] = orders
+        .each_ref()
+        .map(|order| order.iter().copied().filter(in_all).collect::<Vec<_>>());
+    if theirs == base || theirs == ours {
+        return;
+    }
+    if ours != base {
+        conflicts.push(format!("{} order", T::KIND));
+        return;
+    }
+
+    let slots: Vec<usize> = (0..merged.len())
+        .filter(|idx| in_all(&merged[*idx].id()))
+        .collect();
+    let records: Vec<T> = theirs
+        .iter()
+        .filter_map(|id| merged.iter().find(|r| r.id() == *id).cloned())
+        .collect();
+    for (slot, record) in slots.into_iter().zip(records) {
+        merged[slot] = record;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(todos: &[(usize, &str)]) -> Store {
+        Store {
+            todos: todos
+                .iter()
+                .map(|(id, text)| {
+                    let mut todo = Todo::new(*id, text.to_string());
+                    todo.created_at = 0;
+                    todo
+                })
+                .collect(),
+            workspaces: Vec::new(),
+        }
+    }
+
+    fn texts(store: &Store) -> Vec<(usize, &str)> {
+        store
+            .todos
+            .iter()
+            .map(|t| (t.id, t.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn takes_the_fields_each_side_changed() {
+        let base = store(&[(0, "Deploy"), (1, "Write docs")]);
+        let mut ours = base.clone();
+        ours.todos[0].text = String::from("Deploy to prod");
+        let mut theirs = base.clone();
+        theirs.todos[0].set_done(true);
+        theirs.todos[1].tags.push(String::from("docs"));
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.todos[0].text, "Deploy to prod");
+        assert!(merged.todos[0].done);
+        assert_eq!(merged.todos[1].tags, vec!["docs"]);
+    }
+
+    #[test]
+    fn keeps_ours_on_conflicts() {
+        let base = store(&[(0, "Deploy")]);
+        let mut ours = base.clone();
+        ours.todos[0].text = String::from("Deploy on Monday");
+        let mut theirs = base.clone();
+        theirs.todos[0].text = String::from("Deploy on Friday");
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert_eq!(texts(&merged), vec![(0, "Deploy on Monday")]);
+        assert_eq!(conflicts, vec!["todo #0: text"]);
+    }
+
+    #[test]
+    fn applies_deletions_and_renumbers_new_todos() {
+        let base = store(&[(0, "Deploy"), (1, "Write docs")]);
+        let ours = store(&[(0, "Deploy"), (1, "Write docs"), (2, "Ours")]);
+        let theirs = store(&[(1, "Write docs"), (2, "Theirs")]);
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            texts(&merged),
+            vec![(1, "Write docs"), (3, "Ours"), (2, "Theirs")]
+        );
+    }
+
+    #[test]
+    fn moves_todos_along_with_renumbered_workspaces() {
+        let base = store(&[]);
+        let mut ours = store(&[(0, "Ours")]);
+        ours.workspaces
+            .push(Workspace::new(0, String::from("Home")));
+        ours.todos[0].workspace = Some(0);
+        let mut theirs = base.clone();
+        theirs
+            .workspaces
+            .push(Workspace::new(0, String::from("Work")));
+
+        let (merged, _) = merge(&base, &ours, &theirs);
+        let ids: Vec<_> = merged
+            .workspaces
+            .iter()
+            .map(|w| (w.id, w.title.as_str()))
+            .collect();
+        assert_eq!(ids, vec![(1, "Home"), (0, "Work")]);
+        assert_eq!(merged.todos[0].workspace, Some(1));
+    }
+
+    fn workspaces(titles: &[(usize, &str)]) -> Store {
+        Store {
+            todos: Vec::new(),
+            workspaces: titles
+                .iter()
+                .map(|(id, title)| Workspace::new(*id, title.to_string()))
+                .collect(),
+        }
+    }
+
+    fn titles(store: &Store) -> Vec<&str> {
+        store.workspaces.iter().map(|w| w.title.as_str()).collect()
+    }
+
+    #[test]
+    fn takes_the_workspace_order_they_saved() {
+        let base = workspaces(&[(0, "Home"), (1, "Work"), (2, "Garden")]);
+        let mut ours = base.clone();
+        ours.workspaces[2].title = String::from("Yard");
+        ours.workspaces
+            .insert(1, Workspace::new(3, String::from("Ours")));
+        let mut theirs = base.clone();
+        theirs.workspaces.swap(0, 2);
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert!(conflicts.is_empty());
+        assert_eq!(titles(&merged), ["Yard", "Ours", "Work", "Home"]);
+    }
+
+    #[test]
+    fn keeps_our_workspace_order_when_both_sides_moved_them() {
+        let base = workspaces(&[(0, "Home"), (1, "Work"), (2, "Garden")]);
+        let mut ours = base.clone();
+        ours.workspaces.swap(0, 1);
+        let mut theirs = base.clone();
+        theirs.workspaces.swap(1, 2);
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert_eq!(titles(&merged), ["Work", "Home", "Garden"]);
+        assert_eq!(conflicts, ["workspace order"]);
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use crossterm::event::{Event, KeyCode, MouseEventKind};
+use itertools::Itertools;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, List, ListState},
+    Frame,
+};
+
+use lazytodo::{storage, store::Location};
+
+use crate::{
+    input::TextInput,
+    keymap::{Action, Keymap, ACTIONS},
+    panes::{Model, Pane, Ui},
+};
+
+const HISTORY_SIZE: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SortKey {
+    Created,
+    Due,
+    Text,
+    Done,
+}
+
+impl SortKey {
+    const ALL: [(SortKey, &'static str); 4] = [
+        (SortKey::Created, "created"),
+        (SortKey::Due, "due"),
+        (SortKey::Text, "text"),
+        (SortKey::Done, "done"),
+    ];
+}
+
+/// The action for a line typed in the palette, e.g. `move infra` or `add_todo`.
+pub fn parse_command(line: &str) -> Result<Action, String> {
+    let line = line.trim();
+    let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+    let args = args.trim();
+
+    let needs_args = |what: &str| match args.is_empty() {
+        true => Err(format!("`{name}` needs {what}")),
+        false => Ok(args.to_string()),
+    };
+
+    match name {
+        "move" => needs_args("a workspace").map(Action::MoveTodo),
+        "due" => needs_args("a date").map(Action::SetDue),
+        "export" => needs_args("a file").map(Action::Export),
+        "sort" => SortKey::ALL
+            .iter()
+            .find(|(_, key)| *key == args)
+            .map(|(key, _)| Action::SortTodos(*key))
+            .ok_or_else(|| String::from("sort by created, due, text or done")),
+        _ => match Action::from_name(name) {
+            Some(_) if !args.is_empty() => Err(format!("`{name}` takes no arguments")),
+            Some(action) => Ok(action),
+            None => Err(format!("unknown command `{name}`")),
+        },
+    }
+}
+
+/// Adds a command that ran to the recent ones shown first in the palette.
+pub fn remember(location: &Location, line: String) {
+    let mut history = load_history(location);
+    history.retain(|l| *l != line);
+    history.insert(0, line);
+    history.truncate(HISTORY_SIZE);
+    let _ = storage::save(&history, location.history());
+}
+
+fn load_history(location: &Location) -> Vec<String> {
+    storage::load(location.history(), |buffer| bincode::deserialize(buffer)).unwrap_or_default()
+}
+
+/// A line of the palette list.
+pub struct Entry {
+    pub command: String,
+    pub args: Option<&'static str>,
+    pub label: &'static str,
+    pub action: Option<Action>,
+    pub recent: bool,
+}
+
+const ARGUMENT_COMMANDS: [(&str, &str, &str); 4] = [
+    ("move", "<workspace>", "Move todo to workspace"),
+    ("due", "<date>", "Set due date"),
+    (
+        "export",
+        "<file>",
+        "Export to .md, .json, .jsonl, .txt, .ics, .org or .csv",
+    ),
+    ("sort", "created|due|text|done", "Sort todos"),
+];
+
+/// The command palette popup, running the selected command on enter.
+pub struct Palette {
+    input: TextInput,
+    state: ListState,
+    history: Vec<String>,
+    // Keys bound to each action, shown next to the commands
+    keys: HashMap<Action, String>,
+}
+
+impl Palette {
+    pub fn new(keymap: &Keymap, location: &Location) -> Self {
+        let keys = ACTIONS
+            .iter()
+            .map(|(action, _, _)| (action.clone(), keymap.keys_for(action).iter().join(", ")))
+            .collect();
+        Self {
+            input: TextInput::new(),
+            state: ListState::default().with_selected(Some(0)),
+            history: load_history(location),
+            keys,
+        }
+    }
+
+    fn select_next(&mut self) {
+        let len = self.entries().len();
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some((i + 1).min(len.saturating_sub(1))));
+        }
+    }
+
+    fn select_previous(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    /// Recent commands followed by every command, best fuzzy matches first.
+    fn entries(&self) -> Vec<Entry> {
+        let input = self.input.value().trim_start();
+        let (name, args) = input.split_once(' ').unwrap_or((input, ""));
+
+        let recent = self.history.iter().map(|line| Entry {
+            command: line.clone(),
+            args: None,
+            label: "recent",
+            action: None,
+            recent: true,
+        });
+        let actions = ACTIONS.iter().map(|(action, name, label)| Entry {
+            command: name.to_string(),
+            args: None,
+            label,
+            action: Some(action.clone()),
+            recent: false,
+        });
+        let with_args = ARGUMENT_COMMANDS.iter().map(|(name, args, label)| Entry {
+            command: name.to_string(),
+            args: Some(*args),
+            label,
+            action: None,
+            recent: false,
+        });
+
+        if name.is_empty() {
+            return recent.chain(with_args).chain(actions).collect();
+        }
+
+        let mut scored: Vec<(i64, Entry)> = with_args
+            .chain(actions)
+            .filter(|entry| args.is_empty() || entry.args.is_some())
+            .filter_map(|entry| {
+                let candidate = format!("{} {}", entry.command, entry.label);
+                fuzzy_score(name, &candidate).map(|score| (score, entry))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| -score);
+        scored.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// The line to run for the selected entry, or `None` when it still needs arguments.
+    fn selected_line(&mut self) -> Option<String> {
+        let input = self.input.value().trim().to_string();
+        let args = input.split_once(' ').map(|(_, args)| args.trim());
+        let entries = self.entries();
+        let Some(entry) = self.state.selected().and_then(|i| entries.get(i)) else {
+            return Some(input);
+        };
+
+        if entry.recent {
+            return Some(entry.command.clone());
+        }
+        match (entry.args, args) {
+            (Some(_), None | Some("")) => {
+                self.input.set_value(format!("{} ", entry.command));
+                self.state.select(Some(0));
+                None
+            }
+            (_, Some(args)) => Some(format!("{} {args}", entry.command)),
+            (_, None) => Some(entry.command.clone()),
+        }
+    }
+
+    /// Fills the input with the selected command so that arguments can be typed.
+    fn complete(&mut self) {
+        let entries = self.entries();
+        if let Some(entry) = self.state.selected().and_then(|i| entries.get(i)) {
+            self.input.set_value(match entry.args {
+                Some(_) => format!("{} ", entry.command),
+                None => entry.command.clone(),
+            });
+            self.state.select(Some(0));
+        }
+    }
+}
+
+impl Pane for Palette {
+    fn handle_event(&mut self, event: &Event, _: &Model) -> Option<Action> {
+        match event {
+            Event::Key(key) => match key.code {
+                KeyCode::Esc => return Some(Action::Cancel),
+                KeyCode::Enter => return self.selected_line().map(Action::RunCommand),
+                KeyCode::Tab => self.complete(),
+                KeyCode::Down => self.select_next(),
+                KeyCode::Up => self.select_previous(),
+                _ => {
+                    if self.input.handle_key(*key) {
+                        self.state.select(Some(0));
+                    }
+                }
+            },
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::ScrollDown => self.select_next(),
+                MouseEventKind::ScrollUp => self.select_previous(),
+                _ => {}
+            },
+            Event::Paste(text) => {
+                self.input.paste(text);
+                self.state.select(Some(0));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, ui: &Ui, _: &Model) {
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 8,
+            width: area.width * 2 / 3,
+            height: area.height / 2,
+        };
+        let [input_area, list_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(popup_area);
+
+        let input_block = ui.popup_block("Commands");
+        let [prompt_area, text_area] =
+            Layout::horizontal([Constraint::Length(1), Constraint::Min(0)])
+                .areas(input_block.inner(input_area));
+
+        let entries = self.entries();
+        let items = entries.iter().map(|entry| {
+            let command = match entry.args {
+                Some(args) => format!("{} {args}", entry.command),
+                None => entry.command.clone(),
+            };
+            let keys = entry
+                .action
+                .as_ref()
+                .and_then(|action| self.keys.get(action))
+                .cloned()
+                .unwrap_or_default();
+            Line::from(vec![
+                Span::from(format!("{command:<32}")),
+                Span::from(format!("{:<20}", entry.label)),
+                Span::styled(keys, Style::default().fg(ui.colors.muted_fg)),
+            ])
+        });
+        let list = List::new(items)
+            .block(ui.popup_block(""))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(input_block, input_area);
+        frame.render_widget(":", prompt_area);
+        frame.render_stateful_widget(list, list_area, &mut self.state);
+        self.input.render(frame, text_area);
+    }
+
+    fn hints(&self) -> Option<String> {
+        Some(String::from("Run: enter | Complete: tab | Close: esc"))
+    }
+}
+
+/// Scores how well `pattern` matches `candidate` as a subsequence, `None` when it does not.
+///
+/// Consecutive characters and characters at the start of words score higher.
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for c in pattern.to_lowercase().chars() {
+        let idx = (position..candidate.len()).find(|i| candidate[*i] == c)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == idx) {
+            score += 5;
+        }
+        if idx == 0 || matches!(candidate[idx - 1], '_' | ' ' | '-') {
+            score += 3;
+        }
+        score -= (idx - position) as i64;
+        previous = Some(idx);
+        position = idx + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_matches_subsequences() {
+        assert!(fuzzy_score("atd", "add_todo Add todo").is_some());
+        assert!(fuzzy_score("ADD", "add_todo Add todo").is_some());
+        assert_eq!(fuzzy_score("dt", "sort Sort todos"), None);
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts_and_runs() {
+        let score = |pattern| fuzzy_score(pattern, "archive_done Archive done").unwrap();
+        // `ad` at the starts of both words beats `ad` skipping from the first `a`.
+        assert!(score("ad") > fuzzy_score("ad", "xaxxxd").unwrap());
+        assert!(score("arch") > score("acie"));
+        let exact = fuzzy_score("sort", "sort Sort todos").unwrap();
+        let spread = fuzzy_score("sort", "show_other_tab").unwrap();
+        assert!(exact > spread);
+    }
+
+    #[test]
+    fn parses_commands_with_arguments() {
+        assert_eq!(
+            parse_command("move infra"),
+            Ok(Action::MoveTodo(String::from("infra")))
+        );
+        assert_eq!(
+            parse_command("export todos.md"),
+            Ok(Action::Export(String::from("todos.md")))
+        );
+        assert_eq!(
+            parse_command("sort due"),
+            Ok(Action::SortTodos(SortKey::Due))
+        );
+        assert_eq!(parse_command("archive_done"), Ok(Action::ArchiveDone));
+        assert!(parse_command("due").is_err());
+        assert!(parse_command("archive_done now").is_err());
+        assert!(parse_command("frobnicate").is_err());
+    }
+}
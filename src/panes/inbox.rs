@@ -0,0 +1,87 @@
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Text,
+    widgets::{List, ListState},
+    Frame,
+};
+
+use crate::keymap::{Action, Context};
+
+use super::{cycle, row_at, step, Model, Pane, Ui};
+
+const VIEWS: [&str; 4] = ["Inbox", "Today", "Tomorrow", "This week"];
+
+pub struct Inbox {
+    state: ListState,
+    area: Rect,
+}
+
+impl Inbox {
+    pub fn new() -> Self {
+        Self {
+            state: ListState::default().with_selected(Some(0)),
+            area: Rect::default(),
+        }
+    }
+}
+
+impl Pane for Inbox {
+    fn context(&self) -> Context {
+        Context::Inbox
+    }
+
+    fn handle_event(&mut self, event: &Event, _: &Model) -> Option<Action> {
+        let Event::Mouse(mouse) = event else {
+            return None;
+        };
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let offset = self.state.offset();
+                if let Some(row) = row_at(self.area, 0, offset, 1, mouse.row) {
+                    if row < VIEWS.len() {
+                        self.state.select(Some(row));
+                    }
+                }
+            }
+            MouseEventKind::ScrollDown => {
+                self.state
+                    .select(step(self.state.selected(), 1, VIEWS.len()))
+            }
+            MouseEventKind::ScrollUp => {
+                self.state
+                    .select(step(self.state.selected(), -1, VIEWS.len()))
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn update(&mut self, action: &Action, model: &mut Model) -> Option<Action> {
+        if model.focus != Context::Inbox {
+            return None;
+        }
+        match action {
+            Action::Down => self
+                .state
+                .select(cycle(self.state.selected(), 1, VIEWS.len())),
+            Action::Up => self
+                .state
+                .select(cycle(self.state.selected(), -1, VIEWS.len())),
+            Action::Top => self.state.select_first(),
+            Action::Bottom => self.state.select(Some(VIEWS.len() - 1)),
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, ui: &Ui, model: &Model) {
+        self.area = area;
+        let block = ui.pane_block("[2] Inbox ", model.focus == Context::Inbox);
+        let list = List::new(VIEWS.map(Text::from))
+            .block(block)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.state);
+    }
+}
@@ -0,0 +1,307 @@
+pub mod inbox;
+pub mod popup;
+pub mod status;
+pub mod todos;
+pub mod workspaces;
+
+use std::{io, mem};
+
+use chrono::{DateTime, Local};
+use crossterm::event::Event;
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    widgets::{Block, BorderType},
+    Frame,
+};
+
+use lazytodo::{
+    entities::workspace, scanner::CodeTodo, stats, store::Location, sync, Link, Store, Workspace,
+};
+
+use crate::{
+    keymap::{Action, Context},
+    theme::{TableColors, Theme, PALETTES},
+};
+
+/// A part of the screen, one of the panes of the layout or a popup drawn above them.
+///
+/// The app turns events into actions with `handle_event`, or with the keymap when the pane
+/// returns none, then hands every action to the `update` of every pane. Panes act on the
+/// actions that concern them, on navigation ones only while they have the focus.
+pub trait Pane {
+    /// The keymap context of the pane, whose bindings apply while it has the focus.
+    fn context(&self) -> Context {
+        Context::Global
+    }
+
+    /// Turns an event into an action, possibly updating what the pane shows.
+    ///
+    /// Panes get the key events while they have the focus and the mouse events over them,
+    /// a popup gets every event and is closed once it returns an action.
+    fn handle_event(&mut self, _event: &Event, _model: &Model) -> Option<Action> {
+        None
+    }
+
+    /// Applies an action, returning another one for the app to dispatch.
+    fn update(&mut self, _action: &Action, _model: &mut Model) -> Option<Action> {
+        None
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, ui: &Ui, model: &Model);
+
+    /// A popup the last update opened, shown above the panes until it returns an action.
+    fn take_popup(&mut self) -> Option<Box<dyn Pane>> {
+        None
+    }
+
+    /// Key hints shown in the footer instead of the bindings of the focused pane.
+    fn hints(&self) -> Option<String> {
+        None
+    }
+}
+
+/// The state the panes share: the store and which todos the table shows.
+pub struct Model {
+    pub store: Store,
+    /// Where the store is saved, with its archive and the history of the palette
+    pub location: Location,
+    /// Context of the pane with the focus
+    pub focus: Context,
+    /// Id of the workspace whose todos and colours are shown in the table
+    pub active: Option<usize>,
+    /// Whether the active workspace also shows the todos of its sub-workspaces
+    pub include_subworkspaces: bool,
+    /// Whether the table shows the comments found in the source code
+    pub code_active: bool,
+    /// TODO, FIXME and HACK comments of the project, shown by the "Code" workspace
+    pub code_todos: Vec<CodeTodo>,
+    /// Feedback from the last command, shown in the status pane
+    pub message: Option<String>,
+    /// Link opened in the editor once the current event is handled
+    pub link_to_open: Option<Link>,
+    /// Time of the last tick, shown in the status pane
+    pub now: DateTime<Local>,
+    // The store as last read or saved, what the changes of other processes are merged against
+    base: Store,
+    // Whether changes wait for another process to release the lock of the store
+    unsaved: bool,
+}
+
+impl Model {
+    pub fn new(
+        store: Store,
+        location: Location,
+        code_todos: Vec<CodeTodo>,
+        now: DateTime<Local>,
+    ) -> Self {
+        Self {
+            base: store.clone(),
+            unsaved: false,
+            store,
+            location,
+            focus: Context::Inbox,
+            active: None,
+            include_subworkspaces: true,
+            code_active: false,
+            code_todos,
+            message: None,
+            link_to_open: None,
+            now,
+        }
+    }
+
+    /// Moves the clock to `now`, returning whether the minute shown changed.
+    pub fn tick(&mut self, now: DateTime<Local>) -> bool {
+        let changed = now.timestamp() / 60 != self.now.timestamp() / 60;
+        self.now = now;
+        changed
+    }
+
+    pub fn active_workspace(&self) -> Option<&Workspace> {
+        self.store
+            .workspaces
+            .iter()
+            .find(|w| Some(w.id) == self.active)
+    }
+
+    /// Ids of the workspaces whose todos are shown, `None` when no workspace is active.
+    pub fn active_ids(&self) -> Option<Vec<usize>> {
+        let id = self.active?;
+        if self.include_subworkspaces {
+            Some(workspace::descendants(&self.store.workspaces, id))
+        } else {
+            Some(vec![id])
+        }
+    }
+
+    /// Positions in the store of the todos shown in the table, in display order.
+    pub fn visible_todos(&self) -> Vec<usize> {
+        let todos = &self.store.todos;
+        match self.active_ids() {
+            Some(ids) => (0..todos.len())
+                .filter(|idx| todos[*idx].workspace.is_some_and(|w| ids.contains(&w)))
+                .collect(),
+            None => (0..todos.len()).collect(),
+        }
+    }
+
+    /// Number of rows in the table, code comments included.
+    pub fn table_len(&self) -> usize {
+        if self.code_active {
+            self.code_todos.len()
+        } else {
+            self.visible_todos().len()
+        }
+    }
+
+    /// Open and done todo counts for a workspace and all of its sub-workspaces.
+    pub fn workspace_counts(&self, id: usize) -> (usize, usize) {
+        stats::subtree_counts(&self.store.todos, &self.store.workspaces, id)
+    }
+
+    /// Cycles the colour of the active workspace, returning false when there is none.
+    pub fn cycle_active_workspace_color(&mut self) -> bool {
+        let active = self.active;
+        match self
+            .store
+            .workspaces
+            .iter_mut()
+            .find(|w| Some(w.id) == active)
+        {
+            Some(workspace) => {
+                workspace.color = (workspace.color + 1) % PALETTES.len();
+                self.save();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Saves the store and writes the todos back to the files bound to workspaces.
+    ///
+    /// What other lazytodo processes saved in the meantime is merged in, see [`Self::reload`].
+    pub fn save(&mut self) {
+        self.reload();
+        let errors = sync::write_files(&self.store.todos, &self.store.workspaces);
+        if !errors.is_empty() {
+            self.message = Some(errors.join("\n"));
+        }
+    }
+
+    /// Merges what other processes saved into the store, saving our changes on top.
+    ///
+    /// Fields changed on both sides keep our value and are listed in the status pane. Returns
+    /// whether the store changed, for the screen to be redrawn.
+    pub fn reload(&mut self) -> bool {
+        match self.store.save_merged(&self.location, &mut self.base) {
+            Ok((changed, conflicts)) => {
+                let saved_late = mem::replace(&mut self.unsaved, false);
+                if saved_late {
+                    self.message = None;
+                }
+                if !conflicts.is_empty() {
+                    self.message = Some(format!(
+                        "Also changed by another lazytodo, kept ours: {}",
+                        conflicts.join("; ")
+                    ));
+                }
+                changed || saved_late
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                // Retried on the next tick.
+                self.unsaved = true;
+                self.message = Some(String::from(
+                    "Another lazytodo is saving, changes will be saved once it is done",
+                ));
+                false
+            }
+            Err(err) => {
+                self.message = Some(format!("{}: {err}", self.location.dir().display()));
+                false
+            }
+        }
+    }
+
+    /// Whether changes could not be saved yet because another process held the lock.
+    pub fn unsaved(&self) -> bool {
+        self.unsaved
+    }
+
+    /// Picks up the edits made in the files bound to workspaces since the last run.
+    pub fn sync_files(&mut self) {
+        let Store { todos, workspaces } = &mut self.store;
+        let (changed, mut errors) = sync::merge_files(todos, workspaces, true);
+        if changed {
+            self.reload();
+        }
+        // Also creates the files that do not exist yet.
+        errors.extend(sync::write_files(&self.store.todos, &self.store.workspaces));
+        if !errors.is_empty() {
+            self.message = Some(errors.join("\n"));
+        }
+    }
+}
+
+/// The theme and colours the panes are drawn with.
+pub struct Ui<'a> {
+    pub theme: &'a Theme,
+    pub colors: &'a TableColors,
+}
+
+impl Ui<'_> {
+    /// Border style of a pane, highlighted when it has the focus.
+    pub fn pane_style(&self, focused: bool) -> Style {
+        match focused {
+            true => Style::new().fg(self.colors.focus_color).bold(),
+            false => Style::new().fg(self.colors.border_color),
+        }
+    }
+
+    pub fn pane_block<'a>(&self, title: &'a str, focused: bool) -> Block<'a> {
+        Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(title)
+            .border_style(self.pane_style(focused))
+    }
+
+    pub fn popup_block<'a>(&self, title: &'a str) -> Block<'a> {
+        Block::bordered()
+            .title(title)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::new().fg(self.colors.focus_color))
+            .style(
+                Style::new()
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.buffer_bg),
+            )
+    }
+}
+
+/// Index of the item drawn at line `y` of a bordered list, skipping `header` lines.
+pub fn row_at(area: Rect, header: u16, offset: usize, item_height: usize, y: u16) -> Option<usize> {
+    let top = area.y + 1 + header;
+    let bottom = area.bottom().saturating_sub(1);
+    if y < top || y >= bottom {
+        return None;
+    }
+    Some(offset + (y - top) as usize / item_height)
+}
+
+/// Moves a selection by `delta` without wrapping around.
+pub fn step(selected: Option<usize>, delta: isize, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let current = selected.unwrap_or_default();
+    Some(current.saturating_add_signed(delta).min(len - 1))
+}
+
+/// Moves a selection one row down, or up with a negative `delta`, wrapping around.
+pub fn cycle(selected: Option<usize>, delta: isize, len: usize) -> Option<usize> {
+    match selected {
+        _ if len == 0 => None,
+        Some(i) => Some((i as isize + delta).rem_euclid(len as isize) as usize),
+        None => Some(0),
+    }
+}
@@ -0,0 +1,235 @@
+use crossterm::event::{Event, KeyCode, MouseEventKind};
+use itertools::Itertools;
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    input::TextInput,
+    keymap::{Action, Context, Keymap},
+};
+
+use super::{Model, Pane, Ui};
+
+/// Asks for a line of text, turned into an action on enter.
+pub struct InputPopup {
+    title: &'static str,
+    input: TextInput,
+    // Whether long text wraps on several lines rather than scrolling sideways
+    multiline: bool,
+    submit: Box<dyn Fn(String) -> Action>,
+}
+
+impl InputPopup {
+    pub fn new(
+        title: &'static str,
+        history: &[String],
+        submit: impl Fn(String) -> Action + 'static,
+    ) -> Self {
+        let mut input = TextInput::new();
+        for line in history {
+            input.remember(line);
+        }
+        Self {
+            title,
+            input,
+            multiline: false,
+            submit: Box::new(submit),
+        }
+    }
+
+    pub fn multiline(mut self) -> Self {
+        self.multiline = true;
+        self
+    }
+
+    pub fn with_value(mut self, value: String) -> Self {
+        self.input.set_value(value);
+        self
+    }
+}
+
+impl Pane for InputPopup {
+    fn handle_event(&mut self, event: &Event, _: &Model) -> Option<Action> {
+        match event {
+            Event::Key(key) => match key.code {
+                KeyCode::Esc => return Some(Action::Cancel),
+                KeyCode::Enter => {
+                    let text = self.input.value().trim();
+                    return Some(match text.is_empty() {
+                        true => Action::Cancel,
+                        false => (self.submit)(text.to_string()),
+                    });
+                }
+                _ => {
+                    self.input.handle_key(*key);
+                }
+            },
+            Event::Paste(text) => self.input.paste(text),
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, ui: &Ui, _: &Model) {
+        let popup_area = match self.multiline {
+            true => Rect {
+                x: area.width / 4,
+                y: area.height / 3,
+                width: area.width / 2,
+                height: area.height / 3,
+            },
+            false => Rect {
+                x: area.width / 4,
+                y: area.height.saturating_sub(3) / 2,
+                width: area.width / 2,
+                height: area.height.min(3),
+            },
+        };
+
+        let block = ui.popup_block(self.title);
+        let text_area = block.inner(popup_area);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(block, popup_area);
+        self.input.render(frame, text_area);
+    }
+
+    fn hints(&self) -> Option<String> {
+        Some(String::from("Save: enter | Cancel: esc"))
+    }
+}
+
+/// Keys, label and name of an action.
+type HelpLine = (String, &'static str, &'static str);
+
+/// The key bindings of every context.
+pub struct HelpPopup {
+    // Context names followed by the bindings of their actions
+    sections: Vec<(&'static str, Vec<HelpLine>)>,
+    scroll: u16,
+}
+
+impl HelpPopup {
+    pub fn new(keymap: &Keymap) -> Self {
+        let sections = Context::ALL
+            .into_iter()
+            .map(|context| {
+                let bindings = keymap
+                    .bindings(context)
+                    .into_iter()
+                    .map(|(action, keys)| (keys.iter().join(", "), action.label(), action.name()))
+                    .collect_vec();
+                (context.name(), bindings)
+            })
+            .filter(|(_, bindings)| !bindings.is_empty())
+            .collect();
+        Self {
+            sections,
+            scroll: 0,
+        }
+    }
+}
+
+impl Pane for HelpPopup {
+    fn handle_event(&mut self, event: &Event, _: &Model) -> Option<Action> {
+        match event {
+            Event::Key(key) => match key.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+                    return Some(Action::Cancel)
+                }
+                KeyCode::Char('j') | KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
+                KeyCode::Char('k') | KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::Char('g') | KeyCode::Home => self.scroll = 0,
+                KeyCode::Char('G') | KeyCode::End => self.scroll = u16::MAX,
+                _ => {}
+            },
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::ScrollDown => self.scroll = self.scroll.saturating_add(1),
+                MouseEventKind::ScrollUp => self.scroll = self.scroll.saturating_sub(1),
+                _ => {}
+            },
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, ui: &Ui, _: &Model) {
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 10,
+            width: area.width * 2 / 3,
+            height: area.height * 4 / 5,
+        };
+
+        let mut lines = Vec::new();
+        for (context, bindings) in &self.sections {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::from(*context).bold());
+            for (keys, label, name) in bindings {
+                lines.push(Line::from(vec![
+                    Span::from(format!("  {keys:<16}")),
+                    Span::from(format!("{label:<20}")),
+                    Span::styled(*name, Style::default().fg(ui.colors.muted_fg)),
+                ]));
+            }
+        }
+
+        // Keep the last line at the bottom of the popup when scrolled past the end.
+        let max_scroll = (lines.len() as u16).saturating_sub(popup_area.height.saturating_sub(2));
+        self.scroll = self.scroll.min(max_scroll);
+
+        let popup = Paragraph::new(lines)
+            .scroll((self.scroll, 0))
+            .block(ui.popup_block("Help"));
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(popup, popup_area);
+    }
+
+    fn hints(&self) -> Option<String> {
+        Some(String::from("Scroll: j/k | Close: esc"))
+    }
+}
+
+/// Asks before quitting when `confirm_quit` is set.
+pub struct QuitPopup;
+
+impl Pane for QuitPopup {
+    fn handle_event(&mut self, event: &Event, _: &Model) -> Option<Action> {
+        match event {
+            Event::Key(key) => match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => Some(Action::Exit),
+                _ => Some(Action::Cancel),
+            },
+            _ => None,
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, ui: &Ui, _: &Model) {
+        let popup_area = Rect {
+            x: area.width.saturating_sub(30) / 2,
+            y: area.height.saturating_sub(3) / 2,
+            width: area.width.min(30),
+            height: area.height.min(3),
+        };
+
+        let popup = Paragraph::new("Quit lazytodo? (y/n)")
+            .centered()
+            .block(ui.popup_block("Quit"));
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(popup, popup_area);
+    }
+
+    fn hints(&self) -> Option<String> {
+        Some(String::from("Quit: y | Cancel: any key"))
+    }
+}
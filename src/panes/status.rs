@@ -0,0 +1,30 @@
+use ratatui::{
+    layout::Rect,
+    text::Line,
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+
+use crate::keymap::Context;
+
+use super::{Model, Pane, Ui};
+
+/// Feedback from the last command, and the clock.
+pub struct Status;
+
+impl Pane for Status {
+    fn context(&self) -> Context {
+        Context::Status
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, ui: &Ui, model: &Model) {
+        let clock = Line::from(model.now.format(" %H:%M ").to_string()).right_aligned();
+        let block = ui
+            .pane_block("[1] Status ", model.focus == Context::Status)
+            .title(clock);
+        let status = Paragraph::new(model.message.clone().unwrap_or_default())
+            .wrap(Wrap { trim: true })
+            .block(block);
+        frame.render_widget(status, area);
+    }
+}
@@ -0,0 +1,546 @@
+use chrono::{DateTime, Local};
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+use ratatui::{
+    layout::{Constraint, Margin, Position, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Text,
+    widgets::{
+        Block, Cell, HighlightSpacing, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table,
+        TableState,
+    },
+    Frame,
+};
+
+use lazytodo::{dates, entities::todo, store, Todo};
+
+use crate::{
+    keymap::{Action, Context},
+    palette::{self, SortKey},
+};
+
+use super::{cycle, popup::InputPopup, row_at, step, Model, Pane, Ui};
+
+const ITEM_HEIGHT: usize = 2;
+
+/// The todos of the active workspace, or the code comments of the "Code" workspace.
+pub struct Todos {
+    state: TableState,
+    scroll_state: ScrollbarState,
+    area: Rect,
+    scrollbar: Rect,
+    dragging_scrollbar: bool,
+    // Texts of the todos added, for the history of the popup
+    history: Vec<String>,
+    popup: Option<InputPopup>,
+}
+
+impl Todos {
+    pub fn new() -> Self {
+        Self {
+            state: TableState::default().with_selected(0),
+            scroll_state: ScrollbarState::new(0),
+            area: Rect::default(),
+            scrollbar: Rect::default(),
+            dragging_scrollbar: false,
+            history: Vec::new(),
+            popup: None,
+        }
+    }
+
+    /// Position in the store of the todo selected in the table.
+    fn selected_todo(&self, model: &Model) -> Option<usize> {
+        if model.code_active {
+            return None;
+        }
+        let row = self.state.selected()?;
+        model.visible_todos().get(row).copied()
+    }
+
+    fn select_row(&mut self, model: &Model, i: usize) {
+        if i < model.table_len() {
+            self.state.select(Some(i));
+            self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+        }
+    }
+
+    /// Keeps the selection in the table after todos were removed or the workspace changed.
+    fn clamp_selection(&mut self, model: &Model) {
+        let len = model.table_len();
+        if self.state.selected().is_some_and(|row| row >= len) {
+            self.state.select(len.checked_sub(1));
+        }
+    }
+
+    /// Selects the todo at the same relative height as `y` on the scrollbar.
+    fn scroll_to(&mut self, model: &Model, y: u16) {
+        let area = self.scrollbar;
+        let len = model.table_len();
+        if len == 0 || area.height == 0 {
+            return;
+        }
+        let row = y.saturating_sub(area.y) as usize * len / area.height as usize;
+        self.select_row(model, row.min(len - 1));
+    }
+
+    /// Selects the row under a click, returning the action to toggle it when clicked on `[ ]`.
+    fn click(&mut self, model: &Model, position: Position) -> Option<Action> {
+        let offset = self.state.offset();
+        // The header and its margin take the first two lines of the table.
+        let row = row_at(self.area, 2, offset, ITEM_HEIGHT, position.y)?;
+        if row >= model.table_len() {
+            return None;
+        }
+        self.select_row(model, row);
+        let done_column = self.area.x + 1..self.area.x + 4;
+        (!model.code_active && done_column.contains(&position.x)).then_some(Action::ToggleTodo)
+    }
+
+    fn add_todo(&mut self, model: &mut Model, text: &str) {
+        self.history.retain(|t| t != text);
+        self.history.push(text.to_string());
+
+        let todos = &mut model.store.todos;
+        let mut todo = Todo::new(todo::next_id(todos), text.to_string());
+        // Created at the time of the app clock, which the status pane shows.
+        todo.created_at = model.now.timestamp_millis();
+        todo.workspace = model.active;
+        todo.link = todo::find_link(&todo.text);
+        todos.push(todo);
+        model.save();
+    }
+
+    fn toggle_todo(&mut self, model: &mut Model) {
+        if let Some(idx) = self.selected_todo(model) {
+            let item = &mut model.store.todos[idx];
+            item.set_done(!item.done);
+            model.save();
+        }
+    }
+
+    fn delete_todo(&mut self, model: &mut Model) {
+        if let Some(idx) = self.selected_todo(model) {
+            model.store.todos.remove(idx);
+            self.clamp_selection(model);
+            model.save();
+        }
+    }
+
+    fn move_selected_todo(&mut self, model: &mut Model, title: &str) -> Result<(), String> {
+        let idx = self.selected_todo(model).ok_or("no todo selected")?;
+        let list = &model.store.workspaces;
+        let workspace = list
+            .iter()
+            .find(|w| w.title.eq_ignore_ascii_case(title))
+            .or_else(|| {
+                list.iter()
+                    .filter_map(|w| palette::fuzzy_score(title, &w.title).map(|s| (s, w)))
+                    .max_by_key(|(score, _)| *score)
+                    .map(|(_, w)| w)
+            })
+            .ok_or_else(|| format!("no workspace matches `{title}`"))?;
+
+        model.store.todos[idx].workspace = Some(workspace.id);
+        model.save();
+        Ok(())
+    }
+
+    fn set_selected_todo_due(&mut self, model: &mut Model, date: &str) -> Result<(), String> {
+        let idx = self.selected_todo(model).ok_or("no todo selected")?;
+        model.store.todos[idx].due = match date {
+            "none" | "clear" => None,
+            _ => {
+                let today = model.now.date_naive();
+                let due = dates::parse_date(date, today)
+                    .ok_or_else(|| format!("`{date}` is not a date"))?;
+                Some(dates::to_timestamp(due))
+            }
+        };
+        model.save();
+        Ok(())
+    }
+
+    fn sort_todos(&mut self, model: &mut Model, key: SortKey) {
+        let todos = &mut model.store.todos;
+        match key {
+            SortKey::Created => todos.sort_by_key(|item| item.created_at),
+            // Todos without a due date go last.
+            SortKey::Due => todos.sort_by_key(|item| item.due.unwrap_or(i64::MAX)),
+            SortKey::Text => todos.sort_by_key(|item| item.text.to_lowercase()),
+            SortKey::Done => todos.sort_by_key(|item| item.done),
+        }
+        model.save();
+    }
+
+    /// Opens the file of the selected todo or code comment once the event is handled.
+    fn open_selected_link(&mut self, model: &mut Model) {
+        let link = match model.code_active {
+            true => self
+                .state
+                .selected()
+                .and_then(|row| model.code_todos.get(row))
+                .map(|code_todo| code_todo.link.clone()),
+            false => self
+                .selected_todo(model)
+                .and_then(|idx| model.store.todos[idx].link.clone()),
+        };
+        match link {
+            Some(link) => model.link_to_open = Some(link),
+            None => model.message = Some(String::from("The todo has no link")),
+        }
+    }
+
+    /// Adds a todo linked to the code comment selected in the table.
+    fn promote_code_todo(&mut self, model: &mut Model) {
+        if !model.code_active {
+            return;
+        }
+        let Some(code_todo) = self
+            .state
+            .selected()
+            .and_then(|row| model.code_todos.get(row))
+        else {
+            return;
+        };
+        if let Some(todo) = code_todo.promoted(&model.store.todos) {
+            model.message = Some(format!("Already promoted to todo #{}", todo.id));
+            return;
+        }
+        let todo = code_todo.promote(todo::next_id(&model.store.todos));
+        model.message = Some(format!("Promoted to todo #{}", todo.id));
+        model.store.todos.push(todo);
+        model.save();
+    }
+
+    /// Moves done todos out of the list into the archive of the store.
+    ///
+    /// The todos stay in the list when the archive cannot be read or written.
+    fn archive_done(&mut self, model: &mut Model) {
+        let done: Vec<Todo> = model
+            .store
+            .todos
+            .iter()
+            .filter(|t| t.done)
+            .cloned()
+            .collect();
+        if done.is_empty() {
+            return;
+        }
+        let count = done.len();
+        let path = model.location.archive();
+        let archived = store::load_todos(&path).and_then(|mut archive| {
+            archive.extend(done);
+            store::save_todos(&archive, &path)
+        });
+        if let Err(err) = archived {
+            model.message = Some(format!("{}: {err}, nothing was archived", path.display()));
+            return;
+        }
+        model.store.todos.retain(|t| !t.done);
+        model.save();
+
+        self.clamp_selection(model);
+        model.message = Some(format!("Archived {count} todos"));
+    }
+
+    fn render_todo_table(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        ui: &Ui,
+        model: &Model,
+        block: Block,
+    ) {
+        let colors = ui.colors;
+        let visible = model.visible_todos();
+        let rows = visible
+            .iter()
+            .map(|idx| &model.store.todos[*idx])
+            .enumerate()
+            .map(|(i, data)| {
+                let color = match i % 2 {
+                    0 => colors.normal_row_color,
+                    _ => colors.alt_row_color,
+                };
+                let item = data.ref_array();
+                let done_text = if *item.0 { "[x]" } else { "[ ]" };
+                let mut text = data.display_text();
+                if let Some(link) = data
+                    .link
+                    .as_ref()
+                    .filter(|l| !text.contains(&l.to_string()))
+                {
+                    text.push_str(&format!(" → {link}"));
+                }
+
+                let due = data
+                    .due
+                    .and_then(dates::from_timestamp)
+                    .map(|d| d.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+
+                let created_at = DateTime::from_timestamp_millis(*item.2);
+                let created_at = match created_at {
+                    Some(d) => format!("{}", d.with_timezone(&Local).format("%Y-%m-%d %H:%M")),
+                    None => String::from(""),
+                };
+                Row::new(vec![
+                    Cell::from(done_text),
+                    Cell::from(text),
+                    Cell::from(due),
+                    Cell::from(created_at),
+                ])
+                .style(Style::new().fg(colors.row_fg).bg(color))
+                .height(ITEM_HEIGHT as u16)
+            });
+
+        let t = Table::new(
+            rows,
+            [
+                Constraint::Min(3),
+                Constraint::Percentage(100),
+                Constraint::Min(10),
+                Constraint::Min(17),
+            ],
+        )
+        .block(block)
+        .header(
+            Row::new(vec![
+                Cell::from(""),
+                Cell::from(""),
+                Cell::from(Text::from("Due").centered().bold()),
+                Cell::from(Text::from("Created At").centered().bold()),
+            ])
+            .style(Style::default().fg(colors.header_fg).bg(colors.header_bg))
+            .bottom_margin(1),
+        )
+        .column_spacing(1)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(colors.selected_row_style_fg),
+        )
+        .column_highlight_style(Style::default().fg(colors.selected_column_style_fg))
+        .cell_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(colors.selected_cell_style_fg),
+        )
+        .highlight_spacing(HighlightSpacing::Always)
+        .bg(colors.buffer_bg);
+
+        frame.render_stateful_widget(t, area, &mut self.state);
+    }
+
+    /// The comments found by the scanner, marked `✓` once promoted.
+    fn render_code_table(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        ui: &Ui,
+        model: &Model,
+        block: Block,
+    ) {
+        let colors = ui.colors;
+        let rows = model.code_todos.iter().enumerate().map(|(i, code_todo)| {
+            let color = match i % 2 {
+                0 => colors.normal_row_color,
+                _ => colors.alt_row_color,
+            };
+            let promoted = match code_todo.promoted(&model.store.todos) {
+                Some(_) => "✓",
+                None => "",
+            };
+            Row::new(vec![
+                Cell::from(promoted),
+                Cell::from(format!("{} {}", code_todo.keyword, code_todo.text)),
+                Cell::from(code_todo.link.to_string()),
+            ])
+            .style(Style::new().fg(colors.row_fg).bg(color))
+            .height(ITEM_HEIGHT as u16)
+        });
+
+        let t = Table::new(
+            rows,
+            [
+                Constraint::Min(3),
+                Constraint::Percentage(100),
+                Constraint::Min(28),
+            ],
+        )
+        .block(block)
+        .header(
+            Row::new(vec![
+                Cell::from(""),
+                Cell::from(Text::from("Comment").bold()),
+                Cell::from(Text::from("Location").centered().bold()),
+            ])
+            .style(Style::default().fg(colors.header_fg).bg(colors.header_bg))
+            .bottom_margin(1),
+        )
+        .column_spacing(1)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(colors.selected_row_style_fg),
+        )
+        .highlight_spacing(HighlightSpacing::Always)
+        .bg(colors.buffer_bg);
+
+        frame.render_stateful_widget(t, area, &mut self.state);
+    }
+
+    fn render_scrollbar(&mut self, frame: &mut Frame, area: Rect, model: &Model) {
+        let area = area.inner(Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+        self.scrollbar = Rect {
+            x: area.right().saturating_sub(1),
+            width: area.width.min(1),
+            ..area
+        };
+
+        let len = model.table_len();
+        let position = self.state.selected().unwrap_or_default();
+        self.scroll_state = self
+            .scroll_state
+            .content_length(len * ITEM_HEIGHT)
+            .position(position * ITEM_HEIGHT);
+
+        frame.render_stateful_widget(
+            Scrollbar::default()
+                .orientation(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            area,
+            &mut self.scroll_state,
+        );
+    }
+}
+
+impl Pane for Todos {
+    fn context(&self) -> Context {
+        Context::Todos
+    }
+
+    fn handle_event(&mut self, event: &Event, model: &Model) -> Option<Action> {
+        let Event::Mouse(mouse) = event else {
+            return None;
+        };
+        let position = Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) if self.scrollbar.contains(position) => {
+                self.dragging_scrollbar = true;
+                self.scroll_to(model, position.y);
+            }
+            MouseEventKind::Down(MouseButton::Left) => return self.click(model, position),
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging_scrollbar => {
+                self.scroll_to(model, position.y)
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.dragging_scrollbar = false,
+            MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+                let delta = match mouse.kind {
+                    MouseEventKind::ScrollDown => 1,
+                    _ => -1,
+                };
+                if let Some(row) = step(self.state.selected(), delta, model.table_len()) {
+                    self.select_row(model, row);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn update(&mut self, action: &Action, model: &mut Model) -> Option<Action> {
+        self.clamp_selection(model);
+        let result = match action {
+            Action::CreateTodo(text) => {
+                self.add_todo(model, text);
+                Ok(())
+            }
+            Action::MoveTodo(title) => self.move_selected_todo(model, title),
+            Action::SetDue(date) => self.set_selected_todo_due(model, date),
+            Action::SortTodos(key) => {
+                self.sort_todos(model, *key);
+                Ok(())
+            }
+            Action::ArchiveDone => {
+                self.archive_done(model);
+                Ok(())
+            }
+            _ if model.focus != Context::Todos => Ok(()),
+            Action::AddTodo => {
+                self.popup = Some(
+                    InputPopup::new("New Todo", &self.history, Action::CreateTodo).multiline(),
+                );
+                Ok(())
+            }
+            Action::Down | Action::Up => {
+                let delta = if *action == Action::Down { 1 } else { -1 };
+                if let Some(row) = cycle(self.state.selected(), delta, model.table_len()) {
+                    self.select_row(model, row);
+                }
+                Ok(())
+            }
+            Action::Top => {
+                self.select_row(model, 0);
+                Ok(())
+            }
+            Action::Bottom => {
+                let len = model.table_len();
+                self.select_row(model, len.saturating_sub(1));
+                Ok(())
+            }
+            Action::DeleteTodo | Action::ToggleTodo if model.code_active => Err(String::from(
+                "Code comments are read-only, promote them first",
+            )),
+            Action::DeleteTodo => {
+                self.delete_todo(model);
+                Ok(())
+            }
+            Action::ToggleTodo => {
+                self.toggle_todo(model);
+                Ok(())
+            }
+            Action::PromoteTodo => {
+                self.promote_code_todo(model);
+                Ok(())
+            }
+            Action::OpenLink => {
+                self.open_selected_link(model);
+                Ok(())
+            }
+            Action::Right => {
+                self.state.select_next_column();
+                Ok(())
+            }
+            Action::Left => {
+                self.state.select_previous_column();
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            model.message = Some(err);
+        }
+        None
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, ui: &Ui, model: &Model) {
+        self.area = area;
+        self.clamp_selection(model);
+        let block = ui.pane_block("[4] Todos ", model.focus == Context::Todos);
+        match model.code_active {
+            true => self.render_code_table(frame, area, ui, model, block),
+            false => self.render_todo_table(frame, area, ui, model, block),
+        }
+        self.render_scrollbar(frame, area, model);
+    }
+
+    fn take_popup(&mut self) -> Option<Box<dyn Pane>> {
+        self.popup
+            .take()
+            .map(|popup| Box::new(popup) as Box<dyn Pane>)
+    }
+}
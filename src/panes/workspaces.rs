@@ -0,0 +1,390 @@
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, List, ListState},
+    Frame,
+};
+
+use lazytodo::{entities::workspace, Workspace};
+
+use crate::{
+    keymap::{Action, Context},
+    theme::{TableColors, PALETTES},
+};
+
+use super::{cycle, popup::InputPopup, row_at, step, Model, Pane, Ui};
+
+/// The workspace tree, followed by the virtual "Code" workspace.
+pub struct Workspaces {
+    current_tab: u8,
+    state: ListState,
+    area: Rect,
+    // Titles entered in the popups, for their history
+    history: Vec<String>,
+    popup: Option<InputPopup>,
+}
+
+impl Workspaces {
+    pub fn new() -> Self {
+        Self {
+            current_tab: 0,
+            state: ListState::default().with_selected(Some(0)),
+            area: Rect::default(),
+            history: Vec::new(),
+            popup: None,
+        }
+    }
+
+    /// Position in the store of the workspace selected in the tree.
+    fn selected(&self, list: &[Workspace]) -> Option<usize> {
+        let rows = workspace::tree_rows(list);
+        self.state
+            .selected()
+            .and_then(|row| rows.get(row))
+            .map(|row| row.index)
+    }
+
+    /// Rows of the tree, followed by the row of the virtual "Code" workspace.
+    fn row_count(list: &[Workspace]) -> usize {
+        workspace::tree_rows(list).len() + 1
+    }
+
+    fn code_selected(&self, list: &[Workspace]) -> bool {
+        self.state.selected() == Some(workspace::tree_rows(list).len())
+    }
+
+    fn select_id(&mut self, list: &[Workspace], id: usize) {
+        let rows = workspace::tree_rows(list);
+        if let Some(row) = rows.iter().position(|row| list[row.index].id == id) {
+            self.state.select(Some(row));
+        }
+    }
+
+    fn open_input(
+        &mut self,
+        title: &'static str,
+        value: String,
+        submit: impl Fn(String) -> Action + 'static,
+    ) {
+        self.popup = Some(InputPopup::new(title, &self.history, submit).with_value(value));
+    }
+
+    fn new_workspace(&mut self, parent: Option<usize>) {
+        let title = match parent {
+            Some(_) => "New Sub-workspace",
+            None => "New Workspace",
+        };
+        self.open_input(title, String::new(), move |title| Action::CreateWorkspace {
+            title,
+            parent,
+        });
+    }
+
+    fn new_subworkspace(&mut self, model: &Model) {
+        let list = &model.store.workspaces;
+        if let Some(idx) = self.selected(list) {
+            self.new_workspace(Some(list[idx].id));
+        }
+    }
+
+    fn rename_current_workspace(&mut self, model: &Model) {
+        let list = &model.store.workspaces;
+        if let Some(workspace) = self.selected(list).map(|idx| &list[idx]) {
+            let id = workspace.id;
+            self.open_input("Rename Workspace", workspace.title.clone(), move |title| {
+                Action::RenameWorkspaceTo { id, title }
+            });
+        }
+    }
+
+    fn create_workspace(&mut self, model: &mut Model, title: &str, parent: Option<usize>) {
+        let list = &mut model.store.workspaces;
+        let mut workspace = Workspace::new(workspace::next_id(list), title.to_string());
+        workspace.parent = parent;
+        if let Some(parent) = list.iter_mut().find(|w| Some(w.id) == parent) {
+            parent.collapsed = false;
+        }
+        list.push(workspace);
+        self.remember(title);
+        model.save();
+    }
+
+    fn rename_workspace(&mut self, model: &mut Model, id: usize, title: &str) {
+        if let Some(workspace) = model.store.workspaces.iter_mut().find(|w| w.id == id) {
+            workspace.title = title.to_string();
+        }
+        self.remember(title);
+        model.save();
+    }
+
+    fn remember(&mut self, title: &str) {
+        self.history.retain(|t| t != title);
+        self.history.push(title.to_string());
+    }
+
+    /// Deletes the selected workspace, moving its todos and sub-workspaces to its parent.
+    fn delete_current_workspace(&mut self, model: &mut Model) {
+        let Some(idx) = self.selected(&model.store.workspaces) else {
+            return;
+        };
+        let workspace = &model.store.workspaces[idx];
+        let (id, parent) = (workspace.id, workspace.parent);
+        for item in model
+            .store
+            .todos
+            .iter_mut()
+            .filter(|t| t.workspace == Some(id))
+        {
+            item.workspace = parent;
+        }
+
+        workspace::remove(&mut model.store.workspaces, id);
+        if model.active == Some(id) {
+            model.active = None;
+        }
+        let len = Self::row_count(&model.store.workspaces);
+        if self.state.selected().is_some_and(|row| row >= len) {
+            self.state.select(len.checked_sub(1));
+        }
+        model.save();
+    }
+
+    /// Swaps the selected workspace with its next (`offset` 1) or previous (`offset` -1) sibling.
+    fn move_current_workspace(&mut self, model: &mut Model, offset: isize) {
+        let list = &mut model.store.workspaces;
+        let Some(idx) = self.selected(list) else {
+            return;
+        };
+        let id = list[idx].id;
+        let parent = list[idx].parent;
+        let siblings: Vec<usize> = (0..list.len())
+            .filter(|i| list[*i].parent == parent)
+            .collect();
+        let Some(position) = siblings.iter().position(|i| *i == idx) else {
+            return;
+        };
+        if let Some(other) = position
+            .checked_add_signed(offset)
+            .and_then(|p| siblings.get(p))
+        {
+            list.swap(idx, *other);
+            self.select_id(list, id);
+            model.save();
+        }
+    }
+
+    /// Makes the selected workspace a child of the sibling above it.
+    fn indent_current_workspace(&mut self, model: &mut Model) {
+        let list = &mut model.store.workspaces;
+        let Some(idx) = self.selected(list) else {
+            return;
+        };
+        let parent = list[idx].parent;
+        let previous_sibling = list[..idx]
+            .iter()
+            .rev()
+            .find(|w| w.parent == parent)
+            .map(|w| w.id);
+        if let Some(new_parent) = previous_sibling {
+            let id = list[idx].id;
+            list[idx].parent = Some(new_parent);
+            if let Some(parent) = list.iter_mut().find(|w| w.id == new_parent) {
+                parent.collapsed = false;
+            }
+            self.select_id(list, id);
+            model.save();
+        }
+    }
+
+    /// Moves the selected workspace next to its parent.
+    fn outdent_current_workspace(&mut self, model: &mut Model) {
+        let list = &mut model.store.workspaces;
+        let Some(idx) = self.selected(list) else {
+            return;
+        };
+        if let Some(parent) = list[idx].parent {
+            let id = list[idx].id;
+            list[idx].parent = list.iter().find(|w| w.id == parent).and_then(|w| w.parent);
+            self.select_id(list, id);
+            model.save();
+        }
+    }
+
+    /// Applies `change` to the selected workspace and saves the list.
+    fn change_current_workspace(&mut self, model: &mut Model, change: impl FnOnce(&mut Workspace)) {
+        if let Some(idx) = self.selected(&model.store.workspaces) {
+            change(&mut model.store.workspaces[idx]);
+            model.save();
+        }
+    }
+
+    fn toggle_active_workspace(&mut self, model: &mut Model) {
+        let list = &model.store.workspaces;
+        if self.code_selected(list) {
+            model.code_active = !model.code_active;
+            model.active = None;
+        } else if let Some(id) = self.selected(list).map(|idx| list[idx].id) {
+            model.code_active = false;
+            model.active = if model.active == Some(id) {
+                None
+            } else {
+                Some(id)
+            };
+        }
+    }
+
+    fn title<'a>(&self, colors: &TableColors, focused: bool) -> Line<'a> {
+        let tab_style = |tab| match focused && self.current_tab == tab {
+            true => Style::default().fg(colors.focus_color).bold(),
+            false => Style::default().fg(colors.border_color),
+        };
+        Line::from(vec![
+            Span::from("[3] "),
+            Span::styled("Workspaces", tab_style(0)),
+            Span::styled(" - ", Style::default().fg(colors.border_color)),
+            Span::styled("Tags", tab_style(1)),
+            Span::from(" "),
+        ])
+    }
+}
+
+impl Pane for Workspaces {
+    fn context(&self) -> Context {
+        Context::Workspaces
+    }
+
+    fn handle_event(&mut self, event: &Event, model: &Model) -> Option<Action> {
+        let Event::Mouse(mouse) = event else {
+            return None;
+        };
+        let len = Self::row_count(&model.store.workspaces);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let offset = self.state.offset();
+                if let Some(row) = row_at(self.area, 0, offset, 1, mouse.row) {
+                    if row < len {
+                        self.state.select(Some(row));
+                    }
+                }
+            }
+            MouseEventKind::ScrollDown => self.state.select(step(self.state.selected(), 1, len)),
+            MouseEventKind::ScrollUp => self.state.select(step(self.state.selected(), -1, len)),
+            _ => {}
+        }
+        None
+    }
+
+    fn update(&mut self, action: &Action, model: &mut Model) -> Option<Action> {
+        match action {
+            Action::CreateWorkspace { title, parent } => {
+                self.create_workspace(model, title, *parent)
+            }
+            Action::RenameWorkspaceTo { id, title } => self.rename_workspace(model, *id, title),
+            _ if model.focus != Context::Workspaces => {}
+            Action::NewWorkspace => self.new_workspace(None),
+            Action::NewSubworkspace => self.new_subworkspace(model),
+            Action::RenameWorkspace => self.rename_current_workspace(model),
+            Action::DeleteWorkspace => self.delete_current_workspace(model),
+            Action::Down => {
+                let len = Self::row_count(&model.store.workspaces);
+                self.state.select(cycle(self.state.selected(), 1, len));
+            }
+            Action::Up => {
+                let len = Self::row_count(&model.store.workspaces);
+                self.state.select(cycle(self.state.selected(), -1, len));
+            }
+            Action::Top => self.state.select_first(),
+            Action::Bottom => {
+                let len = Self::row_count(&model.store.workspaces);
+                self.state.select(len.checked_sub(1));
+            }
+            Action::MoveWorkspaceDown => self.move_current_workspace(model, 1),
+            Action::MoveWorkspaceUp => self.move_current_workspace(model, -1),
+            Action::IndentWorkspace => self.indent_current_workspace(model),
+            Action::OutdentWorkspace => self.outdent_current_workspace(model),
+            Action::CollapseWorkspace => {
+                self.change_current_workspace(model, |w| w.collapsed = true)
+            }
+            Action::ExpandWorkspace => {
+                self.change_current_workspace(model, |w| w.collapsed = false)
+            }
+            Action::ToggleSubworkspaces => {
+                model.include_subworkspaces = !model.include_subworkspaces
+            }
+            Action::CycleWorkspaceColor => self.change_current_workspace(model, |w| {
+                w.color = (w.color + 1) % PALETTES.len();
+            }),
+            Action::ActivateWorkspace => self.toggle_active_workspace(model),
+            Action::NextTab | Action::PreviousTab => self.current_tab = 1 - self.current_tab,
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, ui: &Ui, model: &Model) {
+        self.area = area;
+        let focused = model.focus == Context::Workspaces;
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(ui.pane_style(focused))
+            .title(self.title(ui.colors, focused));
+
+        let list = &model.store.workspaces;
+        let rows = workspace::tree_rows(list);
+        let workspaces_list = rows.iter().map(|row| {
+            let item = &list[row.index];
+            let marker = if model.active == Some(item.id) {
+                "» "
+            } else {
+                "  "
+            };
+            let fold = match (row.has_children, item.collapsed) {
+                (false, _) => "  ",
+                (true, false) => "▾ ",
+                (true, true) => "▸ ",
+            };
+            let indent = "  ".repeat(row.depth);
+            let title = format!("{marker}{indent}{fold}{}", item.title);
+            let (open, done) = model.workspace_counts(item.id);
+            let palette = &PALETTES[item.color % PALETTES.len()];
+            let style = Style::default().fg(TableColors::workspace_color(ui.theme, palette));
+            let style = if model.active == Some(item.id) {
+                style.bold()
+            } else {
+                style
+            };
+            Line::from(vec![
+                Span::styled(title, style),
+                Span::styled(
+                    format!(" ○{open} ✓{done}"),
+                    Style::default().fg(ui.colors.muted_fg),
+                ),
+            ])
+        });
+        let marker = if model.code_active { "» " } else { "  " };
+        let code_row = Line::from(vec![
+            Span::styled(
+                format!("{marker}  Code"),
+                Style::default().fg(ui.colors.muted_fg),
+            ),
+            Span::styled(
+                format!(" ○{}", model.code_todos.len()),
+                Style::default().fg(ui.colors.muted_fg),
+            ),
+        ]);
+        let workspaces_list = workspaces_list.chain([code_row]);
+
+        let workspaces_list = List::new(workspaces_list)
+            .block(block)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(workspaces_list, area, &mut self.state);
+    }
+
+    fn take_popup(&mut self) -> Option<Box<dyn Pane>> {
+        self.popup
+            .take()
+            .map(|popup| Box::new(popup) as Box<dyn Pane>)
+    }
+}
@@ -0,0 +1,261 @@
+use std::{fs, path::Path};
+
+use ignore::WalkBuilder;
+
+use crate::entities::{todo::Link, Todo};
+
+pub const KEYWORDS: [&str; 3] = ["TODO", "FIXME", "HACK"];
+
+/// Files bigger than this are most likely generated and are not scanned.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// A `TODO`, `FIXME` or `HACK` comment found in the source code.
+pub struct CodeTodo {
+    pub keyword: &'static str,
+    pub text: String,
+    pub link: Link,
+}
+
+impl CodeTodo {
+    /// A real todo that keeps the link to the comment, tagged with the keyword.
+    pub fn promote(&self, id: usize) -> Todo {
+        let text = match self.text.is_empty() {
+            true => format!("{} in {}", self.keyword, self.link),
+            false => self.text.clone(),
+        };
+        let mut todo = Todo::new(id, text);
+        todo.tags = vec![self.keyword.to_lowercase()];
+        todo.link = Some(self.link.clone());
+        todo
+    }
+
+    /// The todo this comment was already promoted to.
+    pub fn promoted<'a>(&self, todos: &'a [Todo]) -> Option<&'a Todo> {
+        todos.iter().find(|t| t.link.as_ref() == Some(&self.link))
+    }
+}
+
+/// Walks the tree under `root`, skipping what `.gitignore` and `.ignore` files exclude.
+pub fn scan(root: &Path) -> Vec<CodeTodo> {
+    let mut todos = Vec::new();
+    let walker = WalkBuilder::new(root).require_git(false).build();
+
+    for entry in walker.flatten() {
+        let path = entry.path();
+        let Some(markers) = comment_markers(path) else {
+            continue;
+        };
+        let too_big = entry
+            .metadata()
+            .map_or(true, |metadata| metadata.len() > MAX_FILE_SIZE);
+        if !entry.file_type().is_some_and(|t| t.is_file()) || too_big {
+            continue;
+        }
+        // Binary and non UTF-8 files are skipped.
+        let Ok(contents) = fs::read_to_string(path) else {
+            continue;
+        };
+
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        for (idx, line) in contents.lines().enumerate() {
+            if let Some((keyword, text)) = find_comment(line, markers) {
+                todos.push(CodeTodo {
+                    keyword,
+                    text,
+                    link: Link {
+                        path: relative.clone(),
+                        line: Some(idx + 1),
+                    },
+                });
+            }
+        }
+    }
+
+    todos.sort_by(|a, b| (&a.link.path, a.link.line).cmp(&(&b.link.path, b.link.line)));
+    todos
+}
+
+/// Strings that start a comment in the language of `path`.
+fn comment_markers(path: &Path) -> Option<&'static [&'static str]> {
+    const C_LIKE: &[&str] = &["//", "/*"];
+    const HASH: &[&str] = &["#"];
+    const DASHES: &[&str] = &["--"];
+    const SEMICOLON: &[&str] = &[";"];
+    const PERCENT: &[&str] = &["%"];
+    const MARKUP: &[&str] = &["<!--"];
+    const CSS: &[&str] = &["/*"];
+
+    let name = path.file_name()?.to_str()?;
+    if matches!(
+        name,
+        "Makefile" | "Dockerfile" | "Justfile" | "CMakeLists.txt"
+    ) {
+        return Some(HASH);
+    }
+    let extension = path.extension()?.to_str()?;
+    match extension {
+        "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "java" | "kt" | "kts" | "go" | "js"
+        | "jsx" | "mjs" | "ts" | "tsx" | "swift" | "scala" | "dart" | "php" | "zig" | "proto"
+        | "groovy" => Some(C_LIKE),
+        "py" | "rb" | "sh" | "bash" | "zsh" | "fish" | "yml" | "yaml" | "toml" | "pl" | "r"
+        | "ex" | "exs" | "nix" | "tf" | "cmake" | "ps1" | "jl" => Some(HASH),
+        "sql" | "lua" | "hs" | "elm" => Some(DASHES),
+        "lisp" | "el" | "clj" | "cljs" | "scm" => Some(SEMICOLON),
+        "tex" | "erl" | "m" => Some(PERCENT),
+        "html" | "xml" | "md" | "vue" | "svelte" => Some(MARKUP),
+        "css" | "scss" | "less" => Some(CSS),
+        _ => None,
+    }
+}
+
+/// Finds a comment starting with a keyword, e.g. `// TODO(ana): retry on timeout`.
+fn find_comment(line: &str, markers: &[&str]) -> Option<(&'static str, String)> {
+    let trimmed = line.trim_start();
+    // Continuation lines of block comments.
+    let block_line = trimmed
+        .strip_prefix('*')
+        .filter(|_| markers.contains(&"/*"));
+
+    let comment = block_line.or_else(|| after_marker(line, markers))?;
+    let comment = comment.trim_start_matches(['/', '*', '#', '-', ';', '%', '!', ' ', '\t']);
+
+    let keyword = KEYWORDS.iter().find(|k| comment.starts_with(*k))?;
+    let rest = &comment[keyword.len()..];
+    // Skips words such as `TODOS` and prose listing the keywords, as in `TODO, FIXME`.
+    if !(rest.is_empty() || rest.starts_with([':', '(', '!', '-', ' ', '\t'])) {
+        return None;
+    }
+    let rest = match rest.strip_prefix('(') {
+        Some(rest) => rest.split_once(')').map_or(rest, |(_, rest)| rest),
+        None => rest,
+    };
+    let text = rest
+        .trim_end()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim_start_matches([':', '-', ' ', '\t'])
+        .trim();
+    Some((keyword, text.to_string()))
+}
+
+/// The text after the first comment marker of `line` that is not in a string literal.
+fn after_marker<'a>(line: &'a str, markers: &[&str]) -> Option<&'a str> {
+    // The end of the string literal the scan is in, and whether `\` escapes in it.
+    let mut string: Option<(String, bool)> = None;
+    let mut skip = 0;
+    for (idx, c) in line.char_indices() {
+        if idx < skip {
+            continue;
+        }
+        let rest = &line[idx..];
+        match &string {
+            Some((_, true)) if c == '\\' => {
+                skip = idx + 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            }
+            Some((end, _)) => {
+                if rest.starts_with(end.as_str()) {
+                    skip = idx + end.len();
+                    string = None;
+                }
+            }
+            None => {
+                if let Some(marker) = markers.iter().find(|m| rest.starts_with(*m)) {
+                    return Some(&rest[marker.len()..]);
+                }
+                let word_start = !line[..idx].ends_with(|c: char| c.is_alphanumeric() || c == '_');
+                let hashes = rest[1..].len() - rest[1..].trim_start_matches('#').len();
+                if c == 'r' && word_start && rest[1 + hashes..].starts_with('"') {
+                    // A Rust raw string, e.g. `r#"…"#`.
+                    string = Some((format!("\"{}", "#".repeat(hashes)), false));
+                    skip = idx + hashes + 2;
+                } else if c == '"' || c == '`' || (c == '\'' && rest[1..].contains('\'')) {
+                    // A `'` that is not closed is a Rust lifetime or a Lisp quote.
+                    string = Some((c.to_string(), true));
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(line: &str, path: &str) -> Option<(&'static str, String)> {
+        find_comment(line, comment_markers(Path::new(path)).unwrap())
+    }
+
+    #[test]
+    fn finds_keyword_comments() {
+        let found = |line| comment(line, "main.rs");
+        assert_eq!(
+            found("    // TODO(ana): retry on timeout"),
+            Some(("TODO", String::from("retry on timeout")))
+        );
+        assert_eq!(
+            found("let x = 1; /* FIXME - overflow */"),
+            Some(("FIXME", String::from("overflow")))
+        );
+        assert_eq!(
+            found(" * HACK: block comment line"),
+            Some(("HACK", String::from("block comment line")))
+        );
+        assert_eq!(found("/// TODO"), Some(("TODO", String::new())));
+        assert_eq!(found("// TODOS are listed here"), None);
+        assert_eq!(found("// Looks for TODO, FIXME and HACK"), None);
+        assert_eq!(found("let todo = TODO;"), None);
+        assert_eq!(
+            comment("<p>Hi</p> <!-- TODO: translate -->", "index.html"),
+            Some(("TODO", String::from("translate")))
+        );
+        assert_eq!(
+            comment("-- FIXME: index this", "schema.sql"),
+            Some(("FIXME", String::from("index this")))
+        );
+        assert_eq!(
+            comment("all: # TODO: parallel", "Makefile"),
+            Some(("TODO", String::from("parallel")))
+        );
+        assert!(comment_markers(Path::new("notes.txt")).is_none());
+    }
+
+    #[test]
+    fn skips_markers_in_string_literals() {
+        let rust = |line| comment(line, "main.rs");
+        assert_eq!(
+            rust(r#"let url = "https://example.com"; // TODO: config"#),
+            Some(("TODO", String::from("config")))
+        );
+        assert_eq!(rust(r#"println!("// TODO: not a comment");"#), None);
+        assert_eq!(
+            rust(r#"let s = "\"// TODO"; // FIXME: escapes"#),
+            Some(("FIXME", String::from("escapes")))
+        );
+        assert_eq!(
+            rust(r#"let quote = '"'; // TODO: chars"#),
+            Some(("TODO", String::from("chars")))
+        );
+        assert_eq!(
+            rust("fn name(&self) -> &'static str { // TODO: lifetimes"),
+            Some(("TODO", String::from("lifetimes")))
+        );
+        assert_eq!(rust("const SQL: &str = `-- TODO`;"), None);
+        assert_eq!(
+            rust(r##"let raw = r#"say "// TODO" here"#; // HACK: raw"##),
+            Some(("HACK", String::from("raw")))
+        );
+        assert_eq!(
+            rust(r#"let raw = r"C:\"; // TODO: no escapes"#),
+            Some(("TODO", String::from("no escapes")))
+        );
+
+        let python = |line| comment(line, "app.py");
+        assert_eq!(python(r#"print('# TODO: not a comment')"#), None);
+        assert_eq!(
+            python(r##"tag = "#{id}"  # HACK: format"##),
+            Some(("HACK", String::from("format")))
+        );
+    }
+}
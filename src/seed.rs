@@ -0,0 +1,242 @@
+//! Fake workspaces and todos, for demos, screenshots and trying the interface on large stores.
+//!
+//! The words come from the tables of `fakeit` but are picked with our own generator: the
+//! functions of `fakeit` draw from a global one seeded with the clock, so the same seed
+//! would not give the same store twice.
+
+use std::ops::Range;
+
+use chrono::{Days, Duration, NaiveDate};
+use fakeit::data::{company, hacker, lorem, person};
+use lazytodo::{dates, entities::todo, Link, Store, Todo, Workspace};
+
+use crate::theme::PALETTES;
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+const MINUTE: i64 = 60 * 1000;
+
+/// SplitMix64, small and the same on every platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn range(&mut self, range: Range<i64>) -> i64 {
+        range.start + (self.next() % (range.end - range.start) as u64) as i64
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a>(&mut self, list: &[&'a str]) -> &'a str {
+        list[self.below(list.len())]
+    }
+}
+
+/// A store of `workspaces` workspaces and `todos` todos created over the year before `today`.
+///
+/// The same arguments always give the same store.
+pub fn generate(workspaces: usize, todos: usize, seed: u64, today: NaiveDate) -> Store {
+    let mut rng = Rng(seed);
+    let mut list = Vec::new();
+    for id in 0..workspaces {
+        let workspace = fake_workspace(&mut rng, id, &list);
+        list.push(workspace);
+    }
+    let workspaces = list;
+
+    // A few tags shared by the todos, so that filtering on one finds something.
+    let mut tags: Vec<String> = (0..8)
+        .map(|_| todo::normalize_tag(&rng.pick(hacker::NOUN).replace(' ', "-")))
+        .collect();
+    tags.sort();
+    tags.dedup();
+
+    let mut todos: Vec<Todo> = (0..todos)
+        .map(|_| fake_todo(&mut rng, &workspaces, &tags, today))
+        .collect();
+    // Ids follow the creation order, as they would in a store filled by hand.
+    todos.sort_by_key(|t| t.created_at);
+    for (id, todo) in todos.iter_mut().enumerate() {
+        todo.id = id;
+    }
+    Store { todos, workspaces }
+}
+
+fn fake_workspace(rng: &mut Rng, id: usize, others: &[Workspace]) -> Workspace {
+    let word = capitalize(rng.pick(company::BUZZWORDS));
+    // Titles name workspaces on the command line, so they stay unique.
+    let mut title = word.clone();
+    for number in 2.. {
+        if !others.iter().any(|w| w.title == title) {
+            break;
+        }
+        title = format!("{word} {number}");
+    }
+    let mut workspace = Workspace::new(id, title);
+    workspace.color = rng.below(PALETTES.len());
+    // One in four is nested under a workspace made before it.
+    if id > 0 && rng.chance(25) {
+        workspace.parent = Some(rng.below(id));
+    }
+    workspace
+}
+
+fn fake_todo(rng: &mut Rng, workspaces: &[Workspace], tags: &[String], today: NaiveDate) -> Todo {
+    let mut todo = Todo::new(0, fake_text(rng));
+    let end_of_today = dates::to_timestamp(today) + DAY;
+
+    let created_on = today - Days::new(rng.range(0..365) as u64);
+    todo.created_at = dates::to_timestamp(created_on) + rng.range(8 * 60..20 * 60) * MINUTE;
+    if rng.chance(35) {
+        todo.done = true;
+        todo.completed_at = Some(rng.range(todo.created_at..end_of_today));
+    }
+    if rng.chance(40) {
+        let offset = rng.range(-14..45);
+        todo.due = today
+            .checked_add_signed(Duration::days(offset))
+            .map(dates::to_timestamp);
+    }
+    if !workspaces.is_empty() && rng.chance(85) {
+        todo.workspace = Some(workspaces[rng.below(workspaces.len())].id);
+    }
+    if !tags.is_empty() {
+        for _ in 0..rng.below(4) {
+            let tag = &tags[rng.below(tags.len())];
+            if !todo.tags.contains(tag) {
+                todo.tags.push(tag.clone());
+            }
+        }
+    }
+    if rng.chance(15) {
+        todo.priority = Some((b'A' + rng.below(3) as u8) as char);
+    }
+    if rng.chance(5) {
+        todo.link = Some(Link {
+            path: format!("src/{}.rs", rng.pick(hacker::NOUN).replace(' ', "_")),
+            line: Some(rng.range(1..2000) as usize),
+        });
+    }
+    if rng.chance(10) {
+        let words: Vec<&str> = (0..rng.range(8..40))
+            .map(|_| rng.pick(lorem::WORD))
+            .collect();
+        todo.notes = format!("{}.", capitalize(&words.join(" ")));
+    }
+    todo
+}
+
+/// A short task, a longer one, or now and then a whole sentence that needs wrapping.
+fn fake_text(rng: &mut Rng) -> String {
+    match rng.below(10) {
+        0..=4 => format!(
+            "{} the {} {}",
+            capitalize(rng.pick(hacker::VERB)),
+            rng.pick(hacker::ADJECTIVE),
+            rng.pick(hacker::NOUN)
+        ),
+        5..=7 => {
+            let company = rng.pick(company::NAME);
+            format!(
+                "{} the {} {} {} for {}",
+                capitalize(rng.pick(hacker::VERB)),
+                rng.pick(hacker::ADJECTIVE),
+                rng.pick(hacker::ABBREVIATION),
+                rng.pick(hacker::NOUN),
+                fill(rng, company)
+            )
+        }
+        _ => {
+            let phrase = rng.pick(hacker::PHRASE);
+            fill(rng, phrase)
+        }
+    }
+}
+
+/// Replaces the `{hacker.noun}` style placeholders of the fakeit phrases and company names.
+fn fill(rng: &mut Rng, template: &str) -> String {
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some((before, after)) = rest.split_once('{') {
+        let Some((placeholder, after)) = after.split_once('}') else {
+            break;
+        };
+        text.push_str(before);
+        text.push_str(match placeholder {
+            "hacker.verb" => rng.pick(hacker::VERB),
+            "hacker.ingverb" => rng.pick(hacker::INGVERB),
+            "hacker.adjective" => rng.pick(hacker::ADJECTIVE),
+            "hacker.abbreviation" => rng.pick(hacker::ABBREVIATION),
+            "person.last" => rng.pick(person::LAST),
+            "company.suffix" => rng.pick(company::SUFFIX),
+            _ => rng.pick(hacker::NOUN),
+        });
+        rest = after;
+    }
+    text.push_str(rest);
+    capitalize(&text)
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 14).unwrap()
+    }
+
+    #[test]
+    fn same_seed_same_store() {
+        let store = generate(5, 200, 42, today());
+        assert_eq!(store, generate(5, 200, 42, today()));
+        assert_ne!(store, generate(5, 200, 43, today()));
+        assert_eq!(store.workspaces.len(), 5);
+        assert_eq!(store.todos.len(), 200);
+    }
+
+    #[test]
+    fn todos_are_consistent() {
+        let store = generate(3, 500, 7, today());
+        let end_of_today = dates::to_timestamp(today()) + DAY;
+        for (id, todo) in store.todos.iter().enumerate() {
+            assert_eq!(todo.id, id);
+            assert!(!todo.text.contains('{'), "{}", todo.text);
+            assert!(todo.created_at < end_of_today);
+            assert_eq!(todo.done, todo.completed_at.is_some());
+            if let Some(completed_at) = todo.completed_at {
+                assert!((todo.created_at..end_of_today).contains(&completed_at));
+            }
+            assert!(todo.workspace.is_none_or(|w| w < 3));
+        }
+        assert!(store.todos.iter().any(|t| t.done) && store.todos.iter().any(|t| !t.done));
+    }
+
+    #[test]
+    fn workspace_titles_are_unique() {
+        let store = generate(company::BUZZWORDS.len() * 3, 0, 1, today());
+        let mut titles: Vec<&str> = store.workspaces.iter().map(|w| w.title.as_str()).collect();
+        titles.sort();
+        titles.dedup();
+        assert_eq!(titles.len(), store.workspaces.len());
+    }
+}
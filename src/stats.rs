@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::{
+    dates,
+    entities::{workspace, Todo, Workspace},
+};
+
+/// How many todos were created and completed on a day.
+pub struct Day {
+    pub date: NaiveDate,
+    pub created: usize,
+    pub completed: usize,
+}
+
+/// Open and done todo counts of a workspace, without its sub-workspaces.
+pub struct WorkspaceCounts {
+    /// Titles from the top-level workspace down, empty for todos without a workspace.
+    pub path: Vec<String>,
+    pub open: usize,
+    pub done: usize,
+}
+
+/// The days on which todos were created or completed, in order.
+pub fn per_day(todos: &[Todo]) -> Vec<Day> {
+    let mut days: BTreeMap<NaiveDate, (usize, usize)> = BTreeMap::new();
+    for todo in todos {
+        if let Some(date) = dates::from_timestamp(todo.created_at) {
+            days.entry(date).or_default().0 += 1;
+        }
+        if let Some(date) = todo
+            .completed_at
+            .filter(|_| todo.done)
+            .and_then(dates::from_timestamp)
+        {
+            days.entry(date).or_default().1 += 1;
+        }
+    }
+    days.into_iter()
+        .map(|(date, (created, completed))| Day {
+            date,
+            created,
+            completed,
+        })
+        .collect()
+}
+
+/// Counts for every workspace in tree order, after the todos without a workspace.
+pub fn per_workspace(todos: &[Todo], workspaces: &[Workspace]) -> Vec<WorkspaceCounts> {
+    let mut rows = Vec::new();
+    // Todos of a deleted workspace are counted with the ones without a workspace.
+    let (open, done) = counts(todos.iter().filter(|t| {
+        t.workspace
+            .is_none_or(|id| !workspaces.iter().any(|w| w.id == id))
+    }));
+    if open + done > 0 {
+        rows.push(WorkspaceCounts {
+            path: Vec::new(),
+            open,
+            done,
+        });
+    }
+
+    let mut path: Vec<String> = Vec::new();
+    for row in workspace::all_rows(workspaces) {
+        let workspace = &workspaces[row.index];
+        path.truncate(row.depth);
+        path.push(workspace.title.clone());
+        let (open, done) = counts(todos.iter().filter(|t| t.workspace == Some(workspace.id)));
+        rows.push(WorkspaceCounts {
+            path: path.clone(),
+            open,
+            done,
+        });
+    }
+    rows
+}
+
+/// Open and done todos of the workspace `id` and its sub-workspaces.
+pub fn subtree_counts(todos: &[Todo], workspaces: &[Workspace], id: usize) -> (usize, usize) {
+    let ids = workspace::descendants(workspaces, id);
+    counts(
+        todos
+            .iter()
+            .filter(|t| t.workspace.is_some_and(|w| ids.contains(&w))),
+    )
+}
+
+/// How many of `todos` are open and how many are done.
+pub fn counts<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> (usize, usize) {
+    todos
+        .into_iter()
+        .fold((0, 0), |(open, done), todo| match todo.done {
+            true => (open, done + 1),
+            false => (open + 1, done),
+        })
+}
@@ -0,0 +1,53 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+// Every file written by lazytodo starts with this header so that the files
+// produced before it existed can still be recognised and migrated. The version
+// is bumped whenever the shape of the todos or the workspaces changes.
+const MAGIC: &[u8; 3] = b"LZT";
+const VERSION: u8 = 1;
+
+pub fn save<T: Serialize + ?Sized>(data: &T, filename: impl AsRef<Path>) -> io::Result<()> {
+    let filename = filename.as_ref();
+    if let Some(parent) = filename.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let encoded = bincode::serialize(data).map_err(to_io_error)?;
+    // Written next to the file then moved over it, so that readers never see half a file.
+    let mut temporary = filename.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(MAGIC)?;
+    file.write_all(&[VERSION])?;
+    file.write_all(&encoded)?;
+    fs::rename(temporary, filename)
+}
+
+/// Loads `filename`, falling back to `legacy` for files written without a header.
+pub fn load<T: DeserializeOwned>(
+    filename: impl AsRef<Path>,
+    legacy: impl FnOnce(&[u8]) -> bincode::Result<T>,
+) -> io::Result<T> {
+    let mut file = File::open(filename)?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+
+    match buffer.strip_prefix(MAGIC.as_slice()) {
+        Some([VERSION, rest @ ..]) => bincode::deserialize(rest).map_err(to_io_error),
+        Some([version, ..]) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported file version {version}"),
+        )),
+        _ => legacy(&buffer).map_err(to_io_error),
+    }
+}
+
+fn to_io_error(err: bincode::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
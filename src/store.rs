@@ -0,0 +1,247 @@
+use std::{
+    fs::{self, File, TryLockError},
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::NaiveDate;
+
+use crate::{
+    entities::{
+        todo::LegacyTodo,
+        workspace::{self, LegacyWorkspace},
+        Todo, Workspace,
+    },
+    filter::Filter,
+    merge, storage,
+};
+
+/// The directory of the store, relative to the current directory.
+pub const DIR: &str = ".lazytodo";
+
+/// Where the files of a store are kept, [`DIR`] unless another directory is given.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    dir: PathBuf,
+}
+
+impl Location {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn todos(&self) -> PathBuf {
+        self.dir.join("todos")
+    }
+
+    /// Done todos moved out of the list, see `lazytodo archive`.
+    pub fn archive(&self) -> PathBuf {
+        self.dir.join("archive")
+    }
+
+    pub fn workspaces(&self) -> PathBuf {
+        self.dir.join("workspaces")
+    }
+
+    pub fn lock(&self) -> PathBuf {
+        self.dir.join("lock")
+    }
+
+    /// Recent commands of the palette.
+    pub fn history(&self) -> PathBuf {
+        self.dir.join("history")
+    }
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Self::new(DIR)
+    }
+}
+
+/// The todos and workspaces kept at a [`Location`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Store {
+    pub todos: Vec<Todo>,
+    pub workspaces: Vec<Workspace>,
+}
+
+impl Store {
+    pub fn load(location: &Location) -> io::Result<Self> {
+        Ok(Self {
+            todos: load_todos(location.todos())?,
+            workspaces: load_workspaces(location.workspaces())?,
+        })
+    }
+
+    pub fn save(&self, location: &Location) -> io::Result<()> {
+        save_todos(&self.todos, location.todos())?;
+        save_workspaces(&self.workspaces, location.workspaces())
+    }
+
+    /// Saves the store on top of what other processes saved since `base` was read.
+    ///
+    /// Their changes are merged in field by field, see [`merge`], and `base` becomes the
+    /// saved store. Fails with [`io::ErrorKind::WouldBlock`] while another process holds
+    /// the lock. Returns whether the files had changed, and the conflicts.
+    pub fn save_merged(
+        &mut self,
+        location: &Location,
+        base: &mut Store,
+    ) -> io::Result<(bool, Vec<String>)> {
+        let Some(_lock) = try_lock(location)? else {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "the store is locked by another lazytodo",
+            ));
+        };
+        let theirs = Self::load(location)?;
+        let changed = theirs != *base;
+        let mut conflicts = Vec::new();
+        if changed {
+            match *self == *base {
+                true => *self = theirs.clone(),
+                false => (*self, conflicts) = merge::merge(base, self, &theirs),
+            }
+        }
+        if *self != theirs {
+            self.save(location)?;
+        }
+        *base = self.clone();
+        Ok((changed, conflicts))
+    }
+
+    pub fn todo(&self, id: usize) -> Option<&Todo> {
+        self.todos.iter().find(|t| t.id == id)
+    }
+
+    pub fn todo_mut(&mut self, id: usize) -> Option<&mut Todo> {
+        self.todos.iter_mut().find(|t| t.id == id)
+    }
+
+    /// Finds a workspace by title, ignoring case, or by id.
+    pub fn workspace(&self, name: &str) -> Option<&Workspace> {
+        workspace::find(&self.workspaces, name)
+    }
+
+    /// The todos matching a filter such as `open #ops @infra due:fri`, see [`Filter`].
+    pub fn query(&self, filter: &str, today: NaiveDate) -> Result<Vec<&Todo>, String> {
+        let filter = Filter::parse(filter, &self.workspaces, today)?;
+        Ok(self.todos.iter().filter(|t| filter.matches(t)).collect())
+    }
+}
+
+/// Loads a todo list, an empty one when the file does not exist yet.
+pub fn load_todos(filename: impl AsRef<Path>) -> io::Result<Vec<Todo>> {
+    let todos = storage::load(filename, |buffer| {
+        // Todos had no id before the header, they are numbered in list order.
+        let legacy: Vec<LegacyTodo> = bincode::deserialize(buffer)?;
+        Ok(legacy
+            .into_iter()
+            .enumerate()
+            .map(|(id, todo)| todo.into_todo(id))
+            .collect())
+    });
+    not_found_as_empty(todos)
+}
+
+pub fn save_todos(todos: &[Todo], filename: impl AsRef<Path>) -> io::Result<()> {
+    storage::save(todos, filename)
+}
+
+pub fn load_workspaces(filename: impl AsRef<Path>) -> io::Result<Vec<Workspace>> {
+    let workspaces = storage::load(filename, |buffer| {
+        // Older files gave every workspace the same id, so they are renumbered here.
+        let legacy: Vec<LegacyWorkspace> = bincode::deserialize(buffer)?;
+        Ok(legacy
+            .into_iter()
+            .enumerate()
+            .map(|(idx, w)| Workspace::new(idx, w.title))
+            .collect())
+    });
+    not_found_as_empty(workspaces)
+}
+
+pub fn save_workspaces(workspaces: &[Workspace], filename: impl AsRef<Path>) -> io::Result<()> {
+    storage::save(workspaces, filename)
+}
+
+/// Takes the advisory lock of the store, waiting for other lazytodo processes to release it.
+///
+/// Processes hold the lock from reading the store to saving it, the lock is released when
+/// the file is dropped.
+pub fn lock(location: &Location) -> io::Result<File> {
+    let file = open_lock_file(location)?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Takes the lock of the store, `None` when another process holds it.
+pub fn try_lock(location: &Location) -> io::Result<Option<File>> {
+    let file = open_lock_file(location)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(err)) => Err(err),
+    }
+}
+
+fn open_lock_file(location: &Location) -> io::Result<File> {
+    fs::create_dir_all(location.dir())?;
+    File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(location.lock())
+}
+
+/// When the todos and workspaces were last saved, to notice the saves of other processes.
+pub fn modified(location: &Location) -> [Option<SystemTime>; 2] {
+    [location.todos(), location.workspaces()]
+        .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+}
+
+fn not_found_as_empty<T>(result: io::Result<Vec<T>>) -> io::Result<Vec<T>> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `bytes` to a file of its own, then loads it as a todo list.
+    fn load(name: &str, bytes: &[u8]) -> io::Result<Vec<Todo>> {
+        let path = std::env::temp_dir().join(format!("lazytodo-{}-{name}", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        let todos = load_todos(&path);
+        let _ = fs::remove_file(&path);
+        todos
+    }
+
+    #[test]
+    fn reads_files_written_before_the_header() {
+        let legacy = vec![(false, "Deploy", 1_i64), (true, "Docs", 2_i64)];
+        let todos = load("legacy", &bincode::serialize(&legacy).unwrap()).unwrap();
+        let read: Vec<_> = todos
+            .iter()
+            .map(|t| (t.id, t.done, t.text.as_str(), t.created_at))
+            .collect();
+        assert_eq!(read, vec![(0, false, "Deploy", 1), (1, true, "Docs", 2)]);
+        assert!(todos.iter().all(|t| t.workspace.is_none()));
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let err = load("future", b"LZT\x02").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "unsupported file version 2");
+    }
+}
@@ -0,0 +1,232 @@
+use std::{fs, io};
+
+use crate::{
+    dates,
+    entities::{todo, Todo, Workspace},
+    formats::markdown::{self, Item},
+};
+
+/// Merges the edits made in the Markdown files bound to workspaces into `todos`.
+///
+/// Items are matched to the todos of their workspace by their id marker, or by text when
+/// they have none, and their text is edited in place. Unknown items become new todos and,
+/// when `remove_missing` is set and the file has markers, todos without an item are
+/// deleted. Returns whether `todos` changed, and the files that could not be read.
+pub fn merge_files(
+    todos: &mut Vec<Todo>,
+    workspaces: &[Workspace],
+    remove_missing: bool,
+) -> (bool, Vec<String>) {
+    let mut changed = false;
+    let mut errors = Vec::new();
+    for workspace in workspaces {
+        let Some(path) = &workspace.file else {
+            continue;
+        };
+        match fs::read_to_string(path) {
+            Ok(document) => {
+                let items = markdown::parse_items(&document);
+                changed |= merge(todos, workspace.id, items, remove_missing);
+            }
+            // The file is created by the next write.
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => errors.push(format!("{path}: {err}")),
+        }
+    }
+    (changed, errors)
+}
+
+fn merge(todos: &mut Vec<Todo>, workspace: usize, items: Vec<Item>, remove_missing: bool) -> bool {
+    let mut changed = false;
+    let mut unmatched: Vec<usize> = todos
+        .iter()
+        .filter(|t| t.workspace == Some(workspace))
+        .map(|t| t.id)
+        .collect();
+    // Todos are only found by their text when no item carries their marker.
+    let marked: Vec<usize> = items.iter().filter_map(|item| item.id).collect();
+    // A file written before the markers cannot tell a removed item from an edited one.
+    let remove_missing = remove_missing && !marked.is_empty();
+
+    for item in items {
+        let due = item.due.map(dates::to_timestamp);
+        let found = match item.id {
+            Some(id) if unmatched.contains(&id) => Some(id),
+            // Moved to another workspace, the next write drops the line.
+            Some(id)
+                if todos
+                    .iter()
+                    .any(|t| t.id == id && t.workspace != Some(workspace)) =>
+            {
+                continue
+            }
+            _ => unmatched.iter().copied().find(|id| {
+                !marked.contains(id) && todos.iter().any(|t| t.id == *id && t.text == item.text)
+            }),
+        };
+        let Some(id) = found else {
+            let mut todo = Todo::new(todo::next_id(todos), item.text);
+            todo.workspace = Some(workspace);
+            todo.set_done(item.done);
+            todo.priority = item.priority;
+            todo.tags = item.tags;
+            todo.due = due;
+            todos.push(todo);
+            changed = true;
+            continue;
+        };
+
+        unmatched.retain(|other| *other != id);
+        let Some(todo) = todos.iter_mut().find(|t| t.id == id) else {
+            continue;
+        };
+        if todo.done != item.done {
+            todo.set_done(item.done);
+            changed = true;
+        }
+        if todo.text != item.text
+            || todo.priority != item.priority
+            || todo.tags != item.tags
+            || todo.due != due
+        {
+            todo.text = item.text;
+            todo.priority = item.priority;
+            todo.tags = item.tags;
+            todo.due = due;
+            changed = true;
+        }
+    }
+
+    if remove_missing && !unmatched.is_empty() {
+        todos.retain(|t| !unmatched.contains(&t.id));
+        changed = true;
+    }
+    changed
+}
+
+/// Writes the todos of every bound workspace to its file, returning the files that failed.
+pub fn write_files(todos: &[Todo], workspaces: &[Workspace]) -> Vec<String> {
+    let mut errors = Vec::new();
+    for workspace in workspaces {
+        let Some(path) = &workspace.file else {
+            continue;
+        };
+        let document = match fs::read_to_string(path) {
+            Ok(document) => document,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                format!("# {}\n", workspace.title)
+            }
+            Err(err) => {
+                errors.push(format!("{path}: {err}"));
+                continue;
+            }
+        };
+
+        let items: Vec<&Todo> = todos
+            .iter()
+            .filter(|t| t.workspace == Some(workspace.id))
+            .collect();
+        let updated = markdown::replace_items(&document, &items);
+        if updated != document {
+            if let Err(err) = fs::write(path, updated) {
+                errors.push(format!("{path}: {err}"));
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::todo::Link;
+
+    fn store() -> Vec<Todo> {
+        let mut deploy = Todo::new(0, String::from("Deploy the api"));
+        deploy.workspace = Some(0);
+        deploy.created_at = 1_000;
+        deploy.notes = String::from("Ask before the freeze");
+        deploy.link = Some(Link {
+            path: String::from("src/main.rs"),
+            line: Some(3),
+        });
+        let mut milk = Todo::new(1, String::from("Buy milk"));
+        milk.workspace = Some(0);
+        let mut other = Todo::new(2, String::from("Elsewhere"));
+        other.workspace = Some(1);
+        vec![deploy, milk, other]
+    }
+
+    fn items(document: &str) -> Vec<Item> {
+        markdown::parse_items(document)
+    }
+
+    #[test]
+    fn edited_items_keep_their_todo() {
+        let mut todos = store();
+        let document = "- [x] Deploy the api to production #ops <!-- id:0 -->\n\
+                        - [ ] Buy milk <!-- id:1 -->\n";
+        assert!(merge(&mut todos, 0, items(document), true));
+
+        assert_eq!(todos.len(), 3);
+        let deploy = &todos[0];
+        assert_eq!(deploy.text, "Deploy the api to production");
+        assert!(deploy.done && deploy.completed_at.is_some());
+        assert_eq!(deploy.tags, ["ops"]);
+        assert_eq!(deploy.created_at, 1_000);
+        assert_eq!(deploy.notes, "Ask before the freeze");
+        assert!(deploy.link.is_some());
+        assert!(!merge(&mut todos, 0, items(document), true));
+    }
+
+    #[test]
+    fn text_ending_in_a_number_is_not_a_tag() {
+        let mut todos = store();
+        todos[1].text = String::from("Fix #123");
+        let document = markdown::replace_items("", &[&todos[0], &todos[1]]);
+        assert!(!merge(&mut todos, 0, items(&document), true));
+        assert_eq!(todos[1].text, "Fix #123");
+        assert!(todos[1].tags.is_empty());
+    }
+
+    #[test]
+    fn removed_items_delete_their_todo() {
+        let mut todos = store();
+        let document = "- [ ] Buy oat milk <!-- id:1 -->\n- [ ] Call the bank\n";
+        assert!(merge(&mut todos, 0, items(document), true));
+
+        let texts: Vec<_> = todos.iter().map(|t| (t.id, t.text.as_str())).collect();
+        assert_eq!(
+            texts,
+            [(1, "Buy oat milk"), (2, "Elsewhere"), (3, "Call the bank")]
+        );
+        assert_eq!(todos[2].workspace, Some(0));
+    }
+
+    #[test]
+    fn files_without_markers_delete_nothing() {
+        let mut todos = store();
+        let document = "- [ ] Deploy the API\n- [x] Buy milk\n";
+        assert!(merge(&mut todos, 0, items(document), true));
+
+        let texts: Vec<_> = todos.iter().map(|t| (t.id, t.text.as_str())).collect();
+        assert_eq!(
+            texts,
+            [
+                (0, "Deploy the api"),
+                (1, "Buy milk"),
+                (2, "Elsewhere"),
+                (3, "Deploy the API")
+            ]
+        );
+        assert!(todos[1].done);
+    }
+
+    #[test]
+    fn items_moved_to_another_workspace_are_left_alone() {
+        let mut todos = store();
+        let document = "- [x] Elsewhere <!-- id:2 -->\n- [ ] Buy milk <!-- id:1 -->\n";
+        assert!(!merge(&mut todos, 0, items(document), false));
+        assert!(!todos[2].done);
+    }
+}
@@ -0,0 +1,403 @@
+//! Rendering tests, driving the app with key events and drawing it on a [`TestBackend`].
+//!
+//! The app gets its store, clock and store location injected. Each harness saves to a
+//! directory of its own under the temporary directory, never to `.lazytodo`.
+
+use std::{
+    fs,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{backend::TestBackend, buffer::Buffer, layout::Position, style::Modifier, Terminal};
+
+use lazytodo::{dates, store::Location, Store, Todo, Workspace};
+
+use crate::{config::Config, App};
+
+fn clock() -> DateTime<Local> {
+    Local.with_ymd_and_hms(2025, 3, 14, 9, 30, 0).unwrap()
+}
+
+struct Harness {
+    app: App,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Harness {
+    fn new(store: Store) -> Self {
+        // Tests run in parallel, each one gets its own store directory.
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "lazytodo-{}-app-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        // Saved first as `App::new` would have read it, the app merges its saves into it.
+        let location = Location::new(dir);
+        store.save(&location).unwrap();
+        Self {
+            app: App::with_store(Config::default(), store, location, clock),
+            terminal: Terminal::new(TestBackend::new(80, 24)).unwrap(),
+        }
+    }
+
+    /// The store as saved by the app.
+    fn saved(&self) -> Store {
+        Store::load(&self.app.model.location).unwrap()
+    }
+
+    /// Types each character as a key press.
+    fn press(&mut self, keys: &str) -> &mut Self {
+        for c in keys.chars() {
+            self.key(KeyCode::Char(c));
+        }
+        self
+    }
+
+    fn key(&mut self, code: KeyCode) -> &mut Self {
+        let event = KeyEvent::new(code, KeyModifiers::NONE);
+        self.app.handle_event(&Event::Key(event));
+        self
+    }
+
+    fn render(&mut self) -> &Buffer {
+        self.terminal.draw(|frame| self.app.draw(frame)).unwrap();
+        self.terminal.backend().buffer()
+    }
+}
+
+fn empty_store() -> Store {
+    Store {
+        todos: Vec::new(),
+        workspaces: Vec::new(),
+    }
+}
+
+/// Todos spread over two workspaces, one in three done, created a minute apart.
+fn store_with_todos(count: usize) -> Store {
+    let mut workspaces = vec![
+        Workspace::new(0, String::from("Work")),
+        Workspace::new(1, String::from("Home")),
+    ];
+    workspaces[1].color = 1;
+    let created = clock().timestamp_millis();
+    let todos = (0..count)
+        .map(|id| {
+            let mut todo = Todo::new(id, format!("Todo number {id}"));
+            todo.created_at = created + id as i64 * 60_000;
+            todo.workspace = Some(id % 2);
+            todo.done = id % 3 == 0;
+            if id % 4 == 1 {
+                let due = NaiveDate::from_ymd_opt(2025, 3, 20 + id as u32 % 8).unwrap();
+                todo.due = Some(dates::to_timestamp(due));
+            }
+            todo
+        })
+        .collect();
+    Store { todos, workspaces }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(self.app.model.location.dir());
+    }
+}
+
+/// The text of each line of the buffer, styles left out.
+fn text(buffer: &Buffer) -> Vec<String> {
+    let width = buffer.area.width as usize;
+    buffer
+        .content
+        .chunks(width)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+        .collect()
+}
+
+#[test]
+fn empty_state() {
+    let mut harness = Harness::new(empty_store());
+    assert_eq!(
+        text(harness.render()),
+        [
+            "╭[1] Status ─────── 09:30 ╮╭[4] Todos ─────────────────────────────────────────╮",
+            "│                         ││                          Due        Created At    │",
+            "│                         ││                                                   │",
+            "╰─────────────────────────╯│                                                   │",
+            "╭[2] Inbox ───────────────╮│                                                   │",
+            "│Inbox                    ││                                                   │",
+            "│Today                    ││                                                   │",
+            "│Tomorrow                 ││                                                   │",
+            "│This week                ││                                                   │",
+            "│                         ││                                                   │",
+            "│                         ││                                                   │",
+            "│                         ││                                                   │",
+            "╰─────────────────────────╯│                                                   │",
+            "╭[3] Workspaces - Tags ───╮│                                                   │",
+            "│    Code ○0              ││                                                   │",
+            "│                         ││                                                   │",
+            "│                         ││                                                   │",
+            "│                         ││                                                   │",
+            "│                         ││                                                   │",
+            "│                         ││                                                   │",
+            "╰─────────────────────────╯╰───────────────────────────────────────────────────╯",
+            "╔══════════════════════════════════════════════════════════════════════════════╗",
+            "║               Help: ? | Down: j | Up: k | Top: g g | Bottom: G               ║",
+            "╚══════════════════════════════════════════════════════════════════════════════╝",
+        ]
+    );
+}
+
+#[test]
+fn many_todos_scroll_with_the_selection() {
+    let mut harness = Harness::new(store_with_todos(40));
+    harness.press("4jjjjjjjj");
+    let buffer = harness.render();
+    assert_eq!(
+        text(buffer),
+        [
+            "╭[1] Status ─────── 09:30 ╮╭[4] Todos ─────────────────────────────────────────╮",
+            "│                         ││                          Due        Created At   ║│",
+            "│                         ││                                                  ║│",
+            "╰─────────────────────────╯│[ ] Todo number 1      2025-03-21 2025-03-14 09:31║│",
+            "╭[2] Inbox ───────────────╮│                                                  █│",
+            "│Inbox                    ││[ ] Todo number 2                 2025-03-14 09:32█│",
+            "│Today                    ││                                                  █│",
+            "│Tomorrow                 ││[x] Todo number 3                 2025-03-14 09:33█│",
+            "│This week                ││                                                  ║│",
+            "│                         ││[ ] Todo number 4                 2025-03-14 09:34║│",
+            "│                         ││                                                  ║│",
+            "│                         ││[ ] Todo number 5      2025-03-25 2025-03-14 09:35║│",
+            "╰─────────────────────────╯│                                                  ║│",
+            "╭[3] Workspaces - Tags ───╮│[x] Todo number 6                 2025-03-14 09:36║│",
+            "│    Work ○13 ✓7          ││                                                  ║│",
+            "│    Home ○13 ✓7          ││[ ] Todo number 7                 2025-03-14 09:37║│",
+            "│    Code ○0              ││                                                  ║│",
+            "│                         ││[ ] Todo number 8                 2025-03-14 09:38║│",
+            "│                         ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "╰─────────────────────────╯╰───────────────────────────────────────────────────╯",
+            "╔══════════════════════════════════════════════════════════════════════════════╗",
+            "║Help: ? | Down: j/down | Up: k/up | Top: g g | Bottom: G | Add: a | Delete: d ║",
+            "╚══════════════════════════════════════════════════════════════════════════════╝",
+        ]
+    );
+    // Only the selected todo is highlighted.
+    assert!(buffer[(30, 17)].modifier.contains(Modifier::REVERSED));
+    assert!(!buffer[(30, 15)].modifier.contains(Modifier::REVERSED));
+
+    harness.press("G");
+    let lines = text(harness.render());
+    assert_eq!(
+        lines[17],
+        "│                         ││[x] Todo number 39                2025-03-14 10:09█│"
+    );
+}
+
+#[test]
+fn input_popup_wraps_the_new_todo() {
+    let mut harness = Harness::new(store_with_todos(3));
+    harness
+        .press("4a")
+        .press("Buy oat milk and a very long list of other things for the weekend");
+    assert_eq!(
+        text(harness.render()),
+        [
+            "╭[1] Status ─────── 09:30 ╮╭[4] Todos ─────────────────────────────────────────╮",
+            "│                         ││                          Due        Created At   █│",
+            "│                         ││                                                  █│",
+            "╰─────────────────────────╯│[x] Todo number 0                 2025-03-14 09:30█│",
+            "╭[2] Inbox ───────────────╮│                                                  █│",
+            "│Inbox                    ││[ ] Todo number 1      2025-03-21 2025-03-14 09:31█│",
+            "│Today                    ││                                                  █│",
+            "│Tomorrow                 ││[ ] Todo number 2                 2025-03-14 09:32█│",
+            "│This week          ╭New Todo──────────────────────────────╮                  █│",
+            "│                   │Buy oat milk and a very long list of  │                  █│",
+            "│                   │other things for the weekend          │                  █│",
+            "│                   │                                      │                  █│",
+            "╰───────────────────│                                      │                  █│",
+            "╭[3] Workspaces - Ta│                                      │                  █│",
+            "│    Work ○1 ✓1     │                                      │                  █│",
+            "│    Home ○1 ✓0     ╰──────────────────────────────────────╯                  █│",
+            "│    Code ○0              ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "╰─────────────────────────╯╰───────────────────────────────────────────────────╯",
+            "╔══════════════════════════════════════════════════════════════════════════════╗",
+            "║                           Save: enter | Cancel: esc                          ║",
+            "╚══════════════════════════════════════════════════════════════════════════════╝",
+        ]
+    );
+    let cursor = harness.terminal.get_cursor_position().unwrap();
+    assert_eq!(cursor, Position::new(49, 10));
+
+    // Closing the popup leaves the table as it was.
+    harness.key(KeyCode::Esc);
+    let lines = text(harness.render());
+    assert_eq!(
+        lines[9],
+        "│                         ││                                                  █│"
+    );
+}
+
+#[test]
+fn focused_pane_highlight() {
+    let mut harness = Harness::new(store_with_todos(3));
+    harness.press("3");
+    let buffer = harness.render().clone();
+    let colors = &harness.app.colors;
+
+    // The top left corners of the workspaces and todos panes.
+    let workspaces = &buffer[(0, 13)];
+    let todos = &buffer[(27, 0)];
+    assert_eq!(workspaces.fg, colors.focus_color);
+    assert!(workspaces.modifier.contains(Modifier::BOLD));
+    assert_eq!(todos.fg, colors.border_color);
+    assert!(!todos.modifier.contains(Modifier::BOLD));
+    assert_eq!(
+        text(&buffer)[22],
+        "║Help: ? | Down: j | Up: k | Top: g g | Bottom: G | New: n | New child: N | Ren║"
+    );
+
+    harness.press("4");
+    let buffer = harness.render().clone();
+    let colors = &harness.app.colors;
+    assert_eq!(buffer[(0, 13)].fg, colors.border_color);
+    assert_eq!(buffer[(27, 0)].fg, colors.focus_color);
+}
+
+#[test]
+fn added_todo_is_saved() {
+    let mut harness = Harness::new(store_with_todos(3));
+    harness
+        .press("4a")
+        .press("Buy oat milk")
+        .key(KeyCode::Enter);
+    assert_eq!(
+        text(harness.render()),
+        [
+            "╭[1] Status ─────── 09:30 ╮╭[4] Todos ─────────────────────────────────────────╮",
+            "│                         ││                          Due        Created At   █│",
+            "│                         ││                                                  █│",
+            "╰─────────────────────────╯│[x] Todo number 0                 2025-03-14 09:30█│",
+            "╭[2] Inbox ───────────────╮│                                                  █│",
+            "│Inbox                    ││[ ] Todo number 1      2025-03-21 2025-03-14 09:31█│",
+            "│Today                    ││                                                  █│",
+            "│Tomorrow                 ││[ ] Todo number 2                 2025-03-14 09:32█│",
+            "│This week                ││                                                  █│",
+            "│                         ││[ ] Buy oat milk                  2025-03-14 09:30█│",
+            "│                         ││                                                  █│",
+            "│                         ││                                                  █│",
+            "╰─────────────────────────╯│                                                  █│",
+            "╭[3] Workspaces - Tags ───╮│                                                  █│",
+            "│    Work ○1 ✓1           ││                                                  █│",
+            "│    Home ○1 ✓0           ││                                                  ║│",
+            "│    Code ○0              ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "╰─────────────────────────╯╰───────────────────────────────────────────────────╯",
+            "╔══════════════════════════════════════════════════════════════════════════════╗",
+            "║Help: ? | Down: j/down | Up: k/up | Top: g g | Bottom: G | Add: a | Delete: d ║",
+            "╚══════════════════════════════════════════════════════════════════════════════╝",
+        ]
+    );
+
+    let saved = harness.saved();
+    assert_eq!(saved.todos.len(), 4);
+    assert_eq!(saved.todos[3].text, "Buy oat milk");
+    assert_eq!(saved.todos[3].created_at, clock().timestamp_millis());
+    assert_eq!(saved.workspaces, store_with_todos(3).workspaces);
+}
+
+#[test]
+fn toggled_and_deleted_todos_are_saved() {
+    let mut harness = Harness::new(store_with_todos(3));
+    harness.press("4j ");
+    assert_eq!(
+        text(harness.render()),
+        [
+            "╭[1] Status ─────── 09:30 ╮╭[4] Todos ─────────────────────────────────────────╮",
+            "│                         ││                          Due        Created At   ║│",
+            "│                         ││                                                  ║│",
+            "╰─────────────────────────╯│[x] Todo number 0                 2025-03-14 09:30█│",
+            "╭[2] Inbox ───────────────╮│                                                  █│",
+            "│Inbox                    ││[x] Todo number 1      2025-03-21 2025-03-14 09:31█│",
+            "│Today                    ││                                                  █│",
+            "│Tomorrow                 ││[ ] Todo number 2                 2025-03-14 09:32█│",
+            "│This week                ││                                                  █│",
+            "│                         ││                                                  █│",
+            "│                         ││                                                  █│",
+            "│                         ││                                                  █│",
+            "╰─────────────────────────╯│                                                  █│",
+            "╭[3] Workspaces - Tags ───╮│                                                  █│",
+            "│    Work ○1 ✓1           ││                                                  █│",
+            "│    Home ○0 ✓1           ││                                                  █│",
+            "│    Code ○0              ││                                                  █│",
+            "│                         ││                                                  █│",
+            "│                         ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "╰─────────────────────────╯╰───────────────────────────────────────────────────╯",
+            "╔══════════════════════════════════════════════════════════════════════════════╗",
+            "║Help: ? | Down: j/down | Up: k/up | Top: g g | Bottom: G | Add: a | Delete: d ║",
+            "╚══════════════════════════════════════════════════════════════════════════════╝",
+        ]
+    );
+    assert!(harness.saved().todos[1].done);
+
+    harness.press("d");
+    let lines = text(harness.render());
+    assert_eq!(
+        lines[5],
+        "│Inbox                    ││[ ] Todo number 2                 2025-03-14 09:32█│"
+    );
+    assert_eq!(
+        lines[15],
+        "│    Home ○0 ✓0           ││                                                  █│"
+    );
+    let ids: Vec<usize> = harness.saved().todos.iter().map(|t| t.id).collect();
+    assert_eq!(ids, [0, 2]);
+}
+
+#[test]
+fn renamed_workspace_is_saved() {
+    let mut harness = Harness::new(store_with_todos(3));
+    harness.press("3r");
+    for _ in 0.."Work".len() {
+        harness.key(KeyCode::Backspace);
+    }
+    harness.press("Office").key(KeyCode::Enter);
+    assert_eq!(
+        text(harness.render()),
+        [
+            "╭[1] Status ─────── 09:30 ╮╭[4] Todos ─────────────────────────────────────────╮",
+            "│                         ││                          Due        Created At   █│",
+            "│                         ││                                                  █│",
+            "╰─────────────────────────╯│[x] Todo number 0                 2025-03-14 09:30█│",
+            "╭[2] Inbox ───────────────╮│                                                  █│",
+            "│Inbox                    ││[ ] Todo number 1      2025-03-21 2025-03-14 09:31█│",
+            "│Today                    ││                                                  █│",
+            "│Tomorrow                 ││[ ] Todo number 2                 2025-03-14 09:32█│",
+            "│This week                ││                                                  █│",
+            "│                         ││                                                  █│",
+            "│                         ││                                                  █│",
+            "│                         ││                                                  █│",
+            "╰─────────────────────────╯│                                                  █│",
+            "╭[3] Workspaces - Tags ───╮│                                                  █│",
+            "│    Office ○1 ✓1         ││                                                  █│",
+            "│    Home ○1 ✓0           ││                                                  █│",
+            "│    Code ○0              ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "│                         ││                                                  ║│",
+            "╰─────────────────────────╯╰───────────────────────────────────────────────────╯",
+            "╔══════════════════════════════════════════════════════════════════════════════╗",
+            "║Help: ? | Down: j | Up: k | Top: g g | Bottom: G | New: n | New child: N | Ren║",
+            "╚══════════════════════════════════════════════════════════════════════════════╝",
+        ]
+    );
+    assert_eq!(harness.saved().workspaces[0].title, "Office");
+}
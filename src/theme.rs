@@ -0,0 +1,189 @@
+use std::{collections::HashMap, env, str::FromStr};
+
+use ratatui::style::{palette::tailwind, Color};
+use serde::Deserialize;
+
+/// Accent palettes a workspace can pick from.
+pub const PALETTES: [tailwind::Palette; 4] = [
+    tailwind::BLUE,
+    tailwind::EMERALD,
+    tailwind::INDIGO,
+    tailwind::RED,
+];
+
+/// Base colours of the interface. The accent comes from the active workspace palette
+/// unless the theme fixes one.
+#[derive(Clone)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub alt_background: Color,
+    pub foreground: Color,
+    pub muted: Color,
+    pub border: Color,
+    pub accent: Option<Color>,
+    /// Picks darker accent shades that stay readable on a light background.
+    pub light: bool,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: String::from("dark"),
+            background: tailwind::SLATE.c950,
+            alt_background: tailwind::SLATE.c900,
+            foreground: tailwind::SLATE.c200,
+            muted: tailwind::SLATE.c400,
+            border: tailwind::SLATE.c500,
+            accent: None,
+            light: false,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            name: String::from("light"),
+            background: tailwind::SLATE.c50,
+            alt_background: tailwind::SLATE.c200,
+            foreground: tailwind::SLATE.c900,
+            muted: tailwind::SLATE.c500,
+            border: tailwind::SLATE.c400,
+            accent: None,
+            light: true,
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            name: String::from("high-contrast"),
+            background: Color::Black,
+            alt_background: Color::Black,
+            foreground: Color::White,
+            muted: Color::White,
+            border: Color::White,
+            accent: Some(Color::Yellow),
+            light: false,
+        }
+    }
+
+    /// Leaves every colour to the terminal, see <https://no-color.org>.
+    pub fn no_color() -> Self {
+        Self {
+            name: String::from("no-color"),
+            background: Color::Reset,
+            alt_background: Color::Reset,
+            foreground: Color::Reset,
+            muted: Color::Reset,
+            border: Color::Reset,
+            accent: Some(Color::Reset),
+            light: false,
+        }
+    }
+
+    pub fn built_in() -> Vec<Self> {
+        vec![Self::dark(), Self::light(), Self::high_contrast()]
+    }
+}
+
+/// Whether the `NO_COLOR` environment variable asks for colourless output.
+pub fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// A user theme from the `[themes.<name>]` table of the config file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeConfig {
+    base: Option<String>,
+    background: Option<String>,
+    alt_background: Option<String>,
+    foreground: Option<String>,
+    muted: Option<String>,
+    border: Option<String>,
+    accent: Option<String>,
+    light: Option<bool>,
+}
+
+impl ThemeConfig {
+    /// Builds the theme on top of its `base` (the dark theme by default).
+    pub fn build(self, name: &str, themes: &HashMap<String, Theme>) -> Result<Theme, String> {
+        let base = self.base.as_deref().unwrap_or("dark");
+        let mut theme = themes
+            .get(base)
+            .cloned()
+            .ok_or_else(|| format!("[themes.{name}] unknown base theme `{base}`"))?;
+        theme.name = name.to_string();
+
+        let parse = |value: Option<String>, current: Color| match value {
+            Some(value) => Color::from_str(&value)
+                .map_err(|_| format!("[themes.{name}] `{value}` is not a colour")),
+            None => Ok(current),
+        };
+        theme.background = parse(self.background, theme.background)?;
+        theme.alt_background = parse(self.alt_background, theme.alt_background)?;
+        theme.foreground = parse(self.foreground, theme.foreground)?;
+        theme.muted = parse(self.muted, theme.muted)?;
+        theme.border = parse(self.border, theme.border)?;
+        if let Some(accent) = self.accent {
+            theme.accent = Some(parse(Some(accent), Color::Reset)?);
+        }
+        if let Some(light) = self.light {
+            theme.light = light;
+        }
+        Ok(theme)
+    }
+}
+
+pub struct TableColors {
+    pub buffer_bg: Color,
+    pub header_bg: Color,
+    pub header_fg: Color,
+    pub row_fg: Color,
+    pub selected_row_style_fg: Color,
+    pub selected_column_style_fg: Color,
+    pub selected_cell_style_fg: Color,
+    pub normal_row_color: Color,
+    pub alt_row_color: Color,
+    pub footer_border_color: Color,
+    pub border_color: Color,
+    pub focus_color: Color,
+    pub muted_fg: Color,
+}
+
+impl TableColors {
+    pub fn new(theme: &Theme, color: &tailwind::Palette) -> Self {
+        let (strong, header) = match (theme.accent, theme.light) {
+            (Some(accent), _) => (accent, accent),
+            (None, false) => (color.c400, color.c900),
+            (None, true) => (color.c600, color.c200),
+        };
+        let header_fg = match theme.accent {
+            Some(_) => theme.background,
+            None => theme.foreground,
+        };
+        Self {
+            buffer_bg: theme.background,
+            header_bg: header,
+            header_fg,
+            row_fg: theme.foreground,
+            selected_row_style_fg: strong,
+            selected_column_style_fg: strong,
+            selected_cell_style_fg: theme.accent.unwrap_or(color.c600),
+            normal_row_color: theme.background,
+            alt_row_color: theme.alt_background,
+            footer_border_color: strong,
+            border_color: theme.border,
+            focus_color: strong,
+            muted_fg: theme.muted,
+        }
+    }
+
+    /// Accent used for a workspace title in the drawer.
+    pub fn workspace_color(theme: &Theme, color: &tailwind::Palette) -> Color {
+        match (theme.accent, theme.light) {
+            (Some(Color::Reset), _) => Color::Reset,
+            (_, false) => color.c400,
+            (_, true) => color.c700,
+        }
+    }
+}